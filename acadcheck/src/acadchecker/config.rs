@@ -4,8 +4,29 @@ pub enum Output {
     /// Error that occurs before the checker can run on tests.
     #[serde(rename = "error")]
     Error(String),
+    /// Results of the tests.
     #[serde(rename = "results")]
     Tests(std::collections::BTreeMap<usize, crate::checker::Output>),
+    /// Results of the tests, along with the time limit after the adjustment
+    /// for the language of the solution, if there is a time limit, and what
+    /// the tests wrote on stderr. Only used when there is either of them.
+    #[serde(rename = "results_with_limit")]
+    TestsWithLimit {
+        results: std::collections::BTreeMap<usize, crate::checker::Output>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        time_limit: Option<std::time::Duration>,
+        /// Beginning of what the tests wrote on stderr, for those that did.
+        #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+        stderr: std::collections::BTreeMap<usize, String>,
+    },
+    /// Tests whose input was rejected by the validator, with the reason.
+    #[serde(rename = "invalid")]
+    Invalid(std::collections::BTreeMap<usize, String>),
+    /// The solution violates the
+    /// [`rules`](crate::acadchecker::config::Config::rules) and was not
+    /// compiled.
+    #[serde(rename = "rejected")]
+    Rejected(Vec<String>),
     None,
 }
 
@@ -32,6 +53,85 @@ pub enum SupportedProcessor {
     },
 }
 
+impl SupportedProcessor {
+    /// Name of the language, as used in
+    /// [`time_limits`](crate::acadchecker::config::Config::time_limits).
+    pub fn language(&self) -> &'static str {
+        match self {
+            SupportedProcessor::Gcc { gcc, .. } => match gcc.language() {
+                crate::language::gcc::SupportedGccLanguage::C => "c",
+                crate::language::gcc::SupportedGccLanguage::Cpp => "c++",
+                crate::language::gcc::SupportedGccLanguage::D => "d",
+                crate::language::gcc::SupportedGccLanguage::Go => "go",
+                _ => "gcc",
+            },
+            SupportedProcessor::Python { .. } => "python",
+            SupportedProcessor::Makefile { .. } => "makefile",
+        }
+    }
+
+    /// Compiles the source if needed and returns the command that runs it.
+    pub(crate) fn command(
+        self,
+        source: crate::solution::Source,
+    ) -> Result<Vec<std::ffi::OsString>, crate::language::Error> {
+        // Get arguments
+        let mut args: Option<Vec<std::ffi::OsString>> = None;
+
+        // Get exec path.
+        let mut exec_path: Option<std::path::PathBuf> = None;
+
+        let processor: Box<dyn crate::language::LanguageProcessor> = match self {
+            crate::acadchecker::config::SupportedProcessor::Gcc { gcc, flags, exec } => {
+                // Put the args.
+                args = Some(
+                    flags
+                        .into_iter()
+                        .map(|f| (std::ffi::OsString::from(f)))
+                        .collect::<Vec<_>>(),
+                );
+
+                // Put the exec path.
+                exec_path = Some(exec);
+
+                Box::new(gcc)
+            }
+            crate::acadchecker::config::SupportedProcessor::Python { python, flags } => {
+                // Put the args.
+                args = Some(
+                    flags
+                        .into_iter()
+                        .map(|f| (std::ffi::OsString::from(f)))
+                        .collect::<Vec<_>>(),
+                );
+
+                Box::new(python)
+            }
+            crate::acadchecker::config::SupportedProcessor::Makefile { makefile } => {
+                Box::new(makefile)
+            }
+        };
+
+        let solution = crate::solution::Solution::new(processor, source);
+
+        solution.processor.run(args, solution.source, exec_path)
+    }
+}
+
+/// Adjustment of the time limit for a language: the limit becomes
+/// `limit * multiplier + offset`, with the offset in seconds.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy)]
+pub struct TimeAdjustment {
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+fn default_multiplier() -> f64 {
+    1.0
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct Security {
     pub user: String,
@@ -49,36 +149,130 @@ pub struct Config {
     pub solution: crate::solution::Source,
     pub out_dir: std::path::PathBuf,
     pub security: Option<Security>,
+    /// Tests discovered from directories, added to the `in_refs` of the
+    /// checker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discover: Option<crate::checker::Discovery>,
+    /// Tests generated from a generator and a reference solution, added to
+    /// the `in_refs` of the checker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generate: Option<crate::acadchecker::generate::Generation>,
+    /// Stress testing of the solution, run by
+    /// [`AcadChecker::stress`](crate::acadchecker::AcadChecker::stress).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stress: Option<crate::acadchecker::stress::Stress>,
+    /// Validator run over every input before judging.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validator: Option<crate::acadchecker::validate::Validator>,
+    /// Adjustments of the time limit of the checker, by the language of the
+    /// solution (`c`, `c++`, `d`, `go`, `python` or `makefile`).
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub time_limits: std::collections::BTreeMap<String, TimeAdjustment>,
+    /// Unit-test suite whose test cases are judged from its JUnit-XML report,
+    /// along with the other tests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xunit: Option<crate::acadchecker::xunit::XUnit>,
+    /// Static rules the solution is checked against before it is compiled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<crate::solution::Rules>,
 }
 
 impl Config {
-    pub fn from_json(path: std::path::PathBuf) -> Result<Self, anyhow::Error> {
-        // Check if path exists.
-        if !path.exists() {
-            return Err(anyhow::format_err!("{:?}: not a valid path!", &path));
-        }
+    /// Time limit of the solution, adjusted for its language if it has an
+    /// adjustment. `None` if the checker has no time limit.
+    pub fn time_limit(&self) -> Option<std::time::Duration> {
+        let limit = self.checker.monitors.iter().find_map(|m| match m {
+            crate::checker::MonitorType::Timeout { limit } => Some(*limit),
+            _ => None,
+        })?;
+
+        let adjustment = match self.time_limits.get(self.processor.language()) {
+            Some(a) => a,
+            None => {
+                return Some(limit);
+            }
+        };
+
+        let secs = limit.as_secs_f64() * adjustment.multiplier + adjustment.offset;
+        Some(std::time::Duration::from_secs_f64(secs.max(0.0)))
+    }
+
+    /// Monitors of the checker, with the time limit adjusted for the language
+    /// of the solution.
+    pub fn monitors(&self) -> Vec<crate::checker::MonitorType> {
+        let time_limit = self.time_limit();
+
+        self.checker
+            .monitors
+            .iter()
+            .map(|m| match (m, time_limit) {
+                (crate::checker::MonitorType::Timeout { .. }, Some(limit)) => {
+                    crate::checker::MonitorType::Timeout { limit }
+                }
+                (m, _) => m.clone(),
+            })
+            .collect()
+    }
 
-        // Open the file from the path.
-        let file = std::fs::File::open(path);
+    /// Runner of the tests: outputs in `out_dir`, the monitors with the time
+    /// limit adjusted for the language, and the user and group of `security`.
+    #[cfg(unix)]
+    pub fn runner(&self) -> Result<crate::checker::LocalRunner, anyhow::Error> {
+        let runner =
+            crate::checker::LocalRunner::new(&self.out_dir).with_monitors(&self.monitors());
 
-        // Return if file can't be opened.
-        if let Err(e) = file {
-            return Err(anyhow::format_err!(e.to_string()));
+        match &self.security {
+            Some(s) => runner.with_user(&s.user, &s.group),
+            None => Ok(runner),
         }
+    }
+
+    /// Tests added to the `in_refs` of the checker by discovery and
+    /// generation. Fails if a test number is used twice, by them or by the
+    /// `in_refs`.
+    pub(crate) fn extra_tests(
+        &self,
+    ) -> Result<
+        std::collections::BTreeMap<usize, (std::path::PathBuf, std::path::PathBuf)>,
+        anyhow::Error,
+    > {
+        let mut in_refs = std::collections::BTreeMap::new();
 
-        let reader = std::io::BufReader::new(file.unwrap());
+        let mut add = |tests: std::collections::BTreeMap<_, _>, origin: &str| {
+            for (key, test) in tests {
+                if self.checker.in_refs.contains_key(&key) || in_refs.contains_key(&key) {
+                    return Err(anyhow::format_err!(
+                        "Test {} is {} but is already configured.",
+                        key,
+                        origin
+                    ));
+                }
+                in_refs.insert(key, test);
+            }
+            Ok(())
+        };
 
-        let config: Result<crate::acadchecker::config::Config, serde_json::Error> =
-            serde_json::from_reader(reader);
+        if let Some(discovery) = &self.discover {
+            add(discovery.discover()?, "discovered")?;
+        }
 
-        // Return if configuration cannot be deserialized.
-        if let Err(e) = config {
-            return Err(anyhow::format_err!(
-                "Deserialization from file failed: {}",
-                e.to_string()
-            ));
+        if let Some(generation) = &self.generate {
+            add(generation.generate()?, "generated")?;
         }
 
-        return Ok(config.unwrap());
+        Ok(in_refs)
+    }
+
+    /// Reads the configuration from a JSON file.
+    pub fn from_json(path: std::path::PathBuf) -> Result<Self, anyhow::Error> {
+        crate::util::format::from_file(&path, crate::util::format::Format::Json)
+    }
+
+    /// Reads the configuration from a JSON, TOML or YAML file, chosen by its
+    /// extension (`.json`, `.toml`, `.yaml` or `.yml`). Files without an
+    /// extension are read as JSON.
+    pub fn from_file(path: std::path::PathBuf) -> Result<Self, anyhow::Error> {
+        let format = crate::util::format::Format::from_path(&path)?;
+        crate::util::format::from_file(&path, format)
     }
 }
@@ -0,0 +1,174 @@
+//! Tests generated from a generator program and a reference solution.
+
+/// A program given by its language processor and its source, compiled the
+/// same way as the solution.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Program {
+    pub processor: crate::acadchecker::config::SupportedProcessor,
+    pub source: crate::solution::Source,
+}
+
+/// Tests whose input is the output of a generator run with the arguments of
+/// the test (e.g. `["1000", "--seed", "7"]`), and whose reference is the
+/// output of the reference solution on that input.
+///
+/// Generated files are kept in `cache_dir`, named by a hash of the generator,
+/// the arguments and the reference solution, so they are only generated again
+/// when one of them changes.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Generation {
+    pub generator: Program,
+    pub reference: Program,
+    pub cache_dir: std::path::PathBuf,
+    #[serde(deserialize_with = "crate::util::format::test_keys")]
+    pub tests: std::collections::BTreeMap<usize, Vec<String>>,
+}
+
+impl Generation {
+    /// Generates the missing tests, ready to be used as
+    /// [`in_refs`](crate::checker::CheckerConfig::in_refs). The generator and
+    /// the reference solution are only compiled if a test is not cached.
+    pub fn generate(
+        &self,
+    ) -> Result<
+        std::collections::BTreeMap<usize, (std::path::PathBuf, std::path::PathBuf)>,
+        anyhow::Error,
+    > {
+        if let Err(e) = std::fs::create_dir_all(&self.cache_dir) {
+            return Err(anyhow::format_err!("{:?}: {}", self.cache_dir, e));
+        }
+
+        let generator_hash = hash_program(&self.generator)?;
+        let reference_hash = hash_program(&self.reference)?;
+
+        // Commands of the generator and the reference solution, once compiled.
+        let mut commands: Option<(Vec<std::ffi::OsString>, Vec<std::ffi::OsString>)> = None;
+
+        let mut in_refs = std::collections::BTreeMap::new();
+
+        for (key, args) in &self.tests {
+            let mut hasher = <sha2::Sha256 as sha2::Digest>::new();
+            sha2::Digest::update(&mut hasher, &generator_hash);
+            for arg in args {
+                sha2::Digest::update(&mut hasher, arg.as_bytes());
+                sha2::Digest::update(&mut hasher, [0]);
+            }
+            sha2::Digest::update(&mut hasher, &reference_hash);
+            let hash = format!("{:x}", sha2::Digest::finalize(hasher));
+
+            let input = self.cache_dir.join(format!("{}.in", hash));
+            let reference = self.cache_dir.join(format!("{}.ref", hash));
+
+            if !input.exists() || !reference.exists() {
+                if commands.is_none() {
+                    commands = Some((
+                        compile("Generator", &self.generator)?,
+                        compile("Reference solution", &self.reference)?,
+                    ));
+                }
+                let (generator, solution) = commands.as_ref().unwrap();
+
+                run(generator, args, None, &input)
+                    .map_err(|e| anyhow::format_err!("Generator failed on test {}: {}", key, e))?;
+                run(solution, &[], Some(&input), &reference).map_err(|e| {
+                    anyhow::format_err!("Reference solution failed on test {}: {}", key, e)
+                })?;
+            }
+
+            in_refs.insert(*key, (input, reference));
+        }
+
+        Ok(in_refs)
+    }
+}
+
+pub(crate) fn compile(
+    name: &str,
+    program: &Program,
+) -> Result<Vec<std::ffi::OsString>, anyhow::Error> {
+    program
+        .processor
+        .clone()
+        .command(program.source.clone())
+        .map_err(|e| anyhow::format_err!("{}: {}", name, e))
+}
+
+/// Runs a command with the given arguments and input, writing its output to
+/// `out`. The file only appears once the command succeeded, so a failed run
+/// is never mistaken for a cached one.
+pub(crate) fn run(
+    command: &[std::ffi::OsString],
+    args: &[String],
+    input: Option<&std::path::Path>,
+    out: &std::path::Path,
+) -> Result<(), anyhow::Error> {
+    let partial = out.with_extension("partial");
+
+    let stdin = match input {
+        Some(i) => std::process::Stdio::from(std::fs::File::open(i)?),
+        None => std::process::Stdio::null(),
+    };
+
+    let status = std::process::Command::new(&command[0])
+        .args(&command[1..])
+        .args(args)
+        .stdin(stdin)
+        .stdout(std::fs::File::create(&partial)?)
+        .stderr(std::process::Stdio::null())
+        .status()?;
+
+    if !status.success() {
+        let _ = std::fs::remove_file(&partial);
+        return Err(anyhow::format_err!("Exit status: {}", status));
+    }
+
+    std::fs::rename(&partial, out)?;
+
+    Ok(())
+}
+
+/// Hashes the processor configuration and the source files of a program.
+fn hash_program(program: &Program) -> Result<Vec<u8>, anyhow::Error> {
+    let mut hasher = <sha2::Sha256 as sha2::Digest>::new();
+    sha2::Digest::update(&mut hasher, serde_json::to_vec(&program.processor)?);
+
+    match &program.source {
+        crate::solution::Source::File(file) => {
+            sha2::Digest::update(&mut hasher, read(file)?);
+        }
+        crate::solution::Source::Directory(dir) => {
+            let mut files = Vec::new();
+            let mut dirs = vec![dir.clone()];
+
+            while let Some(current) = dirs.pop() {
+                for entry in std::fs::read_dir(&current)? {
+                    let path = entry?.path();
+                    if path.is_dir() {
+                        dirs.push(path);
+                    } else {
+                        files.push(path);
+                    }
+                }
+            }
+            files.sort();
+
+            for file in files {
+                sha2::Digest::update(
+                    &mut hasher,
+                    file.strip_prefix(dir)?.to_string_lossy().as_bytes(),
+                );
+                sha2::Digest::update(&mut hasher, read(&file)?);
+            }
+        }
+        // The files are only known to the shell, so only the pattern counts.
+        crate::solution::Source::Regex { regex } => {
+            sha2::Digest::update(&mut hasher, regex.as_bytes());
+        }
+    }
+
+    Ok(sha2::Digest::finalize(hasher).to_vec())
+}
+
+fn read(path: &std::path::Path) -> Result<Vec<u8>, anyhow::Error> {
+    std::fs::read(path).map_err(|e| anyhow::format_err!("{:?}: {}", path, e))
+}
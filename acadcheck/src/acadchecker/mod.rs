@@ -4,12 +4,40 @@
 #![cfg_attr(docsrs, doc(cfg(feature = "use-serde")))]
 
 pub mod config;
+pub mod generate;
+pub mod stress;
+pub mod validate;
+pub mod xunit;
 
-pub struct AcadChecker {}
+#[cfg(test)]
+pub mod tests;
+
+/// Bytes of stderr shown in the result of a test.
+const STDERR_EXCERPT: usize = 1024;
+
+pub struct AcadChecker {
+    on_event: Option<crate::checker::EventHandler>,
+}
 
 impl AcadChecker {
     pub fn new() -> Self {
-        Self {}
+        Self { on_event: None }
+    }
+
+    /// Sets a handler that gets the progress of compiling the solution and
+    /// running the tests.
+    pub fn with_events<E>(mut self, on_event: E) -> Self
+    where
+        E: Fn(crate::checker::Event) + Send + Sync + 'static,
+    {
+        self.on_event = Some(std::sync::Arc::new(on_event));
+        self
+    }
+
+    fn emit(&self, event: crate::checker::Event) {
+        if let Some(on_event) = &self.on_event {
+            (on_event)(event);
+        }
     }
 
     /// Run the checker with a given configuration and a runner.
@@ -20,71 +48,261 @@ impl AcadChecker {
     ) -> crate::acadchecker::config::Output
     where
         F: Fn(
-            &Vec<std::ffi::OsString>,
-            std::collections::BTreeMap<usize, &std::path::PathBuf>,
-        ) -> std::collections::BTreeMap<
-            usize,
-            Result<std::path::PathBuf, crate::checker::Error>,
-        >,
+                &Vec<std::ffi::OsString>,
+                std::collections::BTreeMap<usize, &std::path::PathBuf>,
+            ) -> std::collections::BTreeMap<
+                usize,
+                Result<std::path::PathBuf, crate::checker::Error>,
+            > + Sync,
     {
-        // Get arguments
-        let mut args: Option<Vec<std::ffi::OsString>> = None;
-
-        // Get source.
-        let source = config.solution;
-
-        // Get exec path.
-        let mut exec_path: Option<std::path::PathBuf> = None;
-
-        let processor: Box<dyn crate::language::LanguageProcessor> = match config.processor {
-            crate::acadchecker::config::SupportedProcessor::Gcc { gcc, flags, exec } => {
-                // Put the args.
-                args = Some(
-                    flags
-                        .into_iter()
-                        .map(|f| (std::ffi::OsString::from(f)))
-                        .collect::<Vec<_>>(),
-                );
-
-                // Put the exec path.
-                exec_path = Some(exec);
-
-                Box::new(gcc)
+        // Check the source before anything is compiled.
+        if let Some(rules) = &config.rules {
+            match rules.check(&config.solution) {
+                Ok(violations) if violations.is_empty() => {}
+                Ok(violations) => {
+                    return crate::acadchecker::config::Output::Rejected(violations);
+                }
+                Err(e) => {
+                    return crate::acadchecker::config::Output::Error(e.to_string());
+                }
             }
-            crate::acadchecker::config::SupportedProcessor::Python { python, flags } => {
-                // Put the args.
-                args = Some(
-                    flags
-                        .into_iter()
-                        .map(|f| (std::ffi::OsString::from(f)))
-                        .collect::<Vec<_>>(),
-                );
+        }
 
-                Box::new(python)
+        // Add the discovered and generated tests.
+        let extra_tests = match config.extra_tests() {
+            Ok(t) => t,
+            Err(e) => {
+                return crate::acadchecker::config::Output::Error(e.to_string());
             }
-            crate::acadchecker::config::SupportedProcessor::Makefile { makefile } => {
-                Box::new(makefile)
+        };
+
+        let time_limit = config.time_limit();
+        let out_dir = config.out_dir.clone();
+
+        // The xUnit suite runs with the limits and identity of the tests.
+        #[cfg(unix)]
+        let suite_runner = match config.xunit.as_ref().map(|_| config.runner()).transpose() {
+            Ok(r) => r,
+            Err(e) => {
+                return crate::acadchecker::config::Output::Error(e.to_string());
             }
         };
 
-        let solution = crate::solution::Solution::new(processor, source);
+        let mut checker_config = config.checker;
+        checker_config.in_refs.extend(extra_tests);
 
-        let checker_config = config.checker;
+        // Test cases of the xUnit suite are numbered like the other tests.
+        if let Some(xunit) = &config.xunit {
+            if let Some(key) = xunit
+                .tests
+                .keys()
+                .find(|k| checker_config.in_refs.contains_key(k))
+            {
+                return crate::acadchecker::config::Output::Error(format!(
+                    "Test {} is both an xUnit case and an in/ref test.",
+                    key
+                ));
+            }
+        }
+
+        // Reject invalid tests before judging.
+        if let Some(validator) = &config.validator {
+            let inputs = checker_config.in_refs.iter().map(|(k, v)| (*k, &v.0));
+
+            match validator.validate(inputs) {
+                Ok(invalid) if invalid.is_empty() => {}
+                Ok(invalid) => {
+                    return crate::acadchecker::config::Output::Invalid(invalid);
+                }
+                Err(e) => {
+                    return crate::acadchecker::config::Output::Error(e.to_string());
+                }
+            }
+        }
 
         // Get command from the solution processor.
-        let command = match solution.processor.run(args, solution.source, exec_path) {
+        self.emit(crate::checker::Event::CompileStarted);
+
+        let command = match config.processor.command(config.solution) {
             Ok(c) => c,
             Err(e) => {
+                self.emit(crate::checker::Event::CompileFinished {
+                    error: Some(e.to_string()),
+                });
+                self.emit(crate::checker::Event::Done);
                 return crate::acadchecker::config::Output::Error(e.to_string());
             }
         };
 
+        self.emit(crate::checker::Event::CompileFinished { error: None });
+
+        // Run the xUnit suite before the other tests.
+        let xunit_results = match &config.xunit {
+            #[cfg(unix)]
+            Some(xunit) => match suite_runner
+                .as_ref()
+                .ok_or_else(|| anyhow::format_err!("No runner for the xUnit suite."))
+                .and_then(|r| xunit.run(r, &out_dir.join("xunit"), &checker_config.output_type))
+            {
+                Ok(results) => results,
+                Err(e) => {
+                    self.emit(crate::checker::Event::Done);
+                    return crate::acadchecker::config::Output::Error(e.to_string());
+                }
+            },
+            #[cfg(not(unix))]
+            Some(_) => {
+                self.emit(crate::checker::Event::Done);
+                return crate::acadchecker::config::Output::Error(
+                    "xUnit suites are only run on unix.".to_string(),
+                );
+            }
+            None => std::collections::BTreeMap::new(),
+        };
+
+        for (key, output) in &xunit_results {
+            self.emit(crate::checker::Event::TestFinished {
+                test: *key,
+                output: output.clone(),
+            });
+        }
+
         // Build checker
-        let checker = crate::checker::Checker::new(checker_config, runner);
+        let mut checker = crate::checker::Checker::new(checker_config, runner);
+
+        if let Some(on_event) = &self.on_event {
+            let on_event = on_event.clone();
+            checker = checker.with_events(move |event| (on_event)(event));
+        }
 
         // Run it and collect the result :).
-        let checker_results = checker.run(&command);
+        let mut checker_results = checker.run_parallel(&command);
+
+        // Collect what the tests wrote on stderr.
+        let stderr: std::collections::BTreeMap<_, _> = checker_results
+            .iter()
+            .filter(|(_, output)| {
+                !matches!(
+                    output,
+                    crate::checker::Output::Skipped | crate::checker::Output::Ignored { .. }
+                )
+            })
+            .filter_map(|(key, _)| {
+                stderr_excerpt(&out_dir.join(format!("{}.err", key))).map(|s| (*key, s))
+            })
+            .collect();
+
+        checker_results.extend(xunit_results);
+
+        // The plain results keep their shape when there is nothing else.
+        if time_limit.is_none() && stderr.is_empty() {
+            return crate::acadchecker::config::Output::Tests(checker_results);
+        }
+
+        crate::acadchecker::config::Output::TestsWithLimit {
+            results: checker_results,
+            time_limit,
+            stderr,
+        }
+    }
+
+    /// Stress tests the solution against the brute-force solution of the
+    /// [`stress`](crate::acadchecker::config::Config::stress) configuration,
+    /// stopping at the first input on which they disagree.
+    pub fn stress<F>(
+        &self,
+        config: crate::acadchecker::config::Config,
+        runner: F,
+    ) -> crate::acadchecker::stress::Outcome
+    where
+        F: Fn(
+                &Vec<std::ffi::OsString>,
+                std::collections::BTreeMap<usize, &std::path::PathBuf>,
+            ) -> std::collections::BTreeMap<
+                usize,
+                Result<std::path::PathBuf, crate::checker::Error>,
+            > + Sync,
+    {
+        let stress = match &config.stress {
+            Some(s) => s,
+            None => {
+                return crate::acadchecker::stress::Outcome::Error(String::from(
+                    "No stress testing configuration.",
+                ));
+            }
+        };
+
+        self.emit(crate::checker::Event::CompileStarted);
+
+        let command = match config.processor.command(config.solution) {
+            Ok(c) => c,
+            Err(e) => {
+                self.emit(crate::checker::Event::CompileFinished {
+                    error: Some(e.to_string()),
+                });
+                return crate::acadchecker::stress::Outcome::Error(e.to_string());
+            }
+        };
+
+        self.emit(crate::checker::Event::CompileFinished { error: None });
+
+        match stress.run(&command, config.checker, runner) {
+            Ok(outcome) => outcome,
+            Err(e) => crate::acadchecker::stress::Outcome::Error(e.to_string()),
+        }
+    }
+
+    /// Runs the validator over the inputs of every test, without judging.
+    /// Returns the tests that are invalid, if any.
+    pub fn validate(
+        &self,
+        config: crate::acadchecker::config::Config,
+    ) -> crate::acadchecker::config::Output {
+        let validator = match &config.validator {
+            Some(v) => v,
+            None => {
+                return crate::acadchecker::config::Output::Error(String::from(
+                    "No validator configuration.",
+                ));
+            }
+        };
+
+        let extra_tests = match config.extra_tests() {
+            Ok(t) => t,
+            Err(e) => {
+                return crate::acadchecker::config::Output::Error(e.to_string());
+            }
+        };
+
+        // Same tests as the checker would run.
+        let mut inputs = config
+            .checker
+            .in_refs
+            .iter()
+            .map(|(k, v)| (*k, &v.0))
+            .collect::<std::collections::BTreeMap<_, _>>();
+        inputs.extend(extra_tests.iter().map(|(k, v)| (*k, &v.0)));
 
-        crate::acadchecker::config::Output::Tests(checker_results)
+        match validator.validate(inputs) {
+            Ok(invalid) => crate::acadchecker::config::Output::Invalid(invalid),
+            Err(e) => crate::acadchecker::config::Output::Error(e.to_string()),
+        }
     }
 }
+
+/// Reads the first [`STDERR_EXCERPT`] bytes of a stderr file, marking the
+/// excerpt if there is more. Returns `None` if the file is missing or empty.
+fn stderr_excerpt(path: &std::path::Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let mut excerpt =
+        String::from_utf8_lossy(&bytes[..bytes.len().min(STDERR_EXCERPT)]).into_owned();
+    if bytes.len() > STDERR_EXCERPT {
+        excerpt.push_str("\n[truncated]");
+    }
+
+    Some(excerpt)
+}
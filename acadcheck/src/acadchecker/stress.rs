@@ -0,0 +1,156 @@
+//! Stress testing of a solution against a brute-force solution, to find
+//! inputs on which they disagree.
+
+/// Runs a random generator with a new seed on every iteration and compares
+/// the solution with a trusted brute-force solution on its output.
+///
+/// In the arguments of the generator, `{seed}` is replaced by the seed and
+/// `{size}` by a size that grows from 1 to `max_size` over the iterations, so
+/// the first disagreement is found on the smallest inputs. If no argument
+/// contains `{seed}`, the seed is passed as the last argument.
+///
+/// Each seed is judged as test [`TEST`], with the settings of the checker
+/// (comparisons, output type, repeats, I/O, ...). Its input and output are
+/// overwritten by the next seed, so only the last ones are left.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Stress {
+    pub generator: crate::acadchecker::generate::Program,
+    pub brute: crate::acadchecker::generate::Program,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_iterations")]
+    pub iterations: usize,
+    #[serde(default = "default_max_size")]
+    pub max_size: usize,
+    /// Directory for the generated inputs, where the failing input is saved
+    /// as `counterexample.in` (and the brute-force answer as
+    /// `counterexample.ref`).
+    pub work_dir: std::path::PathBuf,
+}
+
+/// Number of the test every seed is judged as.
+pub const TEST: usize = 0;
+
+fn default_iterations() -> usize {
+    1000
+}
+
+fn default_max_size() -> usize {
+    10
+}
+
+/// Result of stress testing.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub enum Outcome {
+    /// No disagreement was found.
+    #[serde(rename = "passed")]
+    Passed { iterations: usize },
+    /// The solution disagreed with the brute-force solution.
+    #[serde(rename = "counterexample")]
+    Counterexample {
+        seed: usize,
+        size: usize,
+        input: std::path::PathBuf,
+        reference: std::path::PathBuf,
+        output: crate::checker::Output,
+    },
+    /// Error that occurs before a counterexample could be found.
+    #[serde(rename = "error")]
+    Error(String),
+}
+
+impl Stress {
+    /// Stress tests the solution `command`, judging each output like the
+    /// checker does with `checker`, whose tests are left out.
+    pub(crate) fn run<F>(
+        &self,
+        command: &Vec<std::ffi::OsString>,
+        mut checker: crate::checker::CheckerConfig<
+            std::path::PathBuf,
+            std::path::PathBuf,
+            Vec<crate::checker::MonitorType>,
+        >,
+        runner: F,
+    ) -> Result<Outcome, anyhow::Error>
+    where
+        F: Fn(
+                &Vec<std::ffi::OsString>,
+                std::collections::BTreeMap<usize, &std::path::PathBuf>,
+            ) -> std::collections::BTreeMap<
+                usize,
+                Result<std::path::PathBuf, crate::checker::Error>,
+            > + Sync,
+    {
+        if let Err(e) = std::fs::create_dir_all(&self.work_dir) {
+            return Err(anyhow::format_err!("{:?}: {}", self.work_dir, e));
+        }
+
+        let generator = crate::acadchecker::generate::compile("Generator", &self.generator)?;
+        let brute = crate::acadchecker::generate::compile("Brute-force solution", &self.brute)?;
+
+        checker.in_refs.clear();
+        checker.alternatives.clear();
+
+        let input = self.work_dir.join("stress.in");
+        let reference = self.work_dir.join("stress.ref");
+
+        for i in 0..self.iterations {
+            let seed = i + 1;
+            let size = 1 + i * self.max_size.max(1) / self.iterations;
+
+            let mut args = self
+                .args
+                .iter()
+                .map(|a| {
+                    a.replace("{seed}", &seed.to_string())
+                        .replace("{size}", &size.to_string())
+                })
+                .collect::<Vec<_>>();
+            if !self.args.iter().any(|a| a.contains("{seed}")) {
+                args.push(seed.to_string());
+            }
+
+            crate::acadchecker::generate::run(&generator, &args, None, &input)
+                .map_err(|e| anyhow::format_err!("Generator failed on seed {}: {}", seed, e))?;
+            crate::acadchecker::generate::run(&brute, &[], Some(&input), &reference).map_err(
+                |e| anyhow::format_err!("Brute-force solution failed on seed {}: {}", seed, e),
+            )?;
+
+            let mut config = checker.clone();
+            config
+                .in_refs
+                .insert(TEST, (input.clone(), reference.clone()));
+
+            let output = match crate::checker::Checker::new(config, &runner)
+                .run(command)
+                .remove(&TEST)
+            {
+                Some(o) => o,
+                None => {
+                    return Err(anyhow::format_err!("Seed {} did not run", seed));
+                }
+            };
+
+            if !output.passed() {
+                let (saved_input, saved_reference) = (
+                    self.work_dir.join("counterexample.in"),
+                    self.work_dir.join("counterexample.ref"),
+                );
+                std::fs::rename(&input, &saved_input)?;
+                std::fs::rename(&reference, &saved_reference)?;
+
+                return Ok(Outcome::Counterexample {
+                    seed,
+                    size,
+                    input: saved_input,
+                    reference: saved_reference,
+                    output,
+                });
+            }
+        }
+
+        Ok(Outcome::Passed {
+            iterations: self.iterations,
+        })
+    }
+}
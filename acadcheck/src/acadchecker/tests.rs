@@ -0,0 +1,508 @@
+use crate::acadchecker::generate::Generation;
+
+#[test]
+fn generated_tests_are_cached() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let generator = dir.path().join("gen.py");
+    std::fs::write(&generator, "import sys\nprint(' '.join(sys.argv[1:]))\n").unwrap();
+    let reference = dir.path().join("ref.py");
+    std::fs::write(&reference, "print(input().upper())\n").unwrap();
+
+    let generation: Generation = serde_json::from_value(serde_json::json!({
+        "generator": {
+            "processor": { "python": { "version": "3", "flags": [] } },
+            "source": { "file": generator }
+        },
+        "reference": {
+            "processor": { "python": { "version": "3", "flags": [] } },
+            "source": { "file": reference }
+        },
+        "cache_dir": dir.path().join("cache"),
+        "tests": { "1": ["gen", "--seed", "7"], "2": ["other"] }
+    }))
+    .unwrap();
+
+    let in_refs = generation.generate().unwrap();
+    let (input, answer) = &in_refs[&1];
+    assert_eq!(std::fs::read_to_string(input).unwrap(), "gen --seed 7\n");
+    assert_eq!(std::fs::read_to_string(answer).unwrap(), "GEN --SEED 7\n");
+
+    // Cached files are used as they are.
+    std::fs::write(answer, "cached\n").unwrap();
+    assert_eq!(generation.generate().unwrap(), in_refs);
+    assert_eq!(std::fs::read_to_string(answer).unwrap(), "cached\n");
+
+    // Changing the reference solution generates the tests again.
+    std::fs::write(&reference, "print(input().lower())\n").unwrap();
+    let (_, answer) = &generation.generate().unwrap()[&1];
+    assert_eq!(std::fs::read_to_string(answer).unwrap(), "gen --seed 7\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn stress_finds_smallest_counterexample() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let write = |name: &str, contents: &str| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    };
+
+    // The solution is wrong for sizes of at least 3.
+    let generator = write("gen.py", "import sys\nprint(sys.argv[1])\n");
+    let brute = write("brute.py", "print(int(input()) * 2)\n");
+    let solution = write("sol.py", "n = int(input())\nprint(n * 2 if n < 3 else 0)\n");
+
+    let python = serde_json::json!({ "python": { "version": "3", "flags": [] } });
+    let config: crate::acadchecker::config::Config = serde_json::from_value(serde_json::json!({
+        "checker": { "monitors": [], "output_type": "none" },
+        "processor": python,
+        "solution": { "file": solution },
+        "out_dir": dir.path().join("out"),
+        "security": null,
+        "stress": {
+            "generator": { "processor": python, "source": { "file": generator } },
+            "brute": { "processor": python, "source": { "file": brute } },
+            "args": ["{size}", "{seed}"],
+            "iterations": 20,
+            "max_size": 5,
+            "work_dir": dir.path().join("stress")
+        }
+    }))
+    .unwrap();
+
+    let runner = crate::checker::LocalRunner::new(dir.path().join("out"));
+    let outcome = crate::acadchecker::AcadChecker::new().stress(config, runner.as_fn());
+
+    match outcome {
+        crate::acadchecker::stress::Outcome::Counterexample {
+            size,
+            input,
+            reference,
+            ..
+        } => {
+            assert_eq!(size, 3);
+            assert_eq!(std::fs::read_to_string(input).unwrap(), "3\n");
+            assert_eq!(std::fs::read_to_string(reference).unwrap(), "6\n");
+        }
+        o => panic!("{:?}", o),
+    }
+
+    // The seeds share one test, so their outputs don't pile up.
+    for entry in std::fs::read_dir(dir.path().join("out")).unwrap() {
+        let name = entry.unwrap().file_name();
+        assert!(name.to_string_lossy().starts_with("0."), "{:?}", name);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn stress_judges_repeated_runs_by_their_verdict() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let write = |name: &str, contents: &str| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    };
+
+    let generator = write("gen.py", "import sys\nprint(sys.argv[1])\n");
+    let brute = write("brute.py", "print(int(input()) * 2)\n");
+
+    let python = serde_json::json!({ "python": { "version": "3", "flags": [] } });
+    let stress = |solution: std::path::PathBuf| {
+        let config: crate::acadchecker::config::Config =
+            serde_json::from_value(serde_json::json!({
+                "checker": { "monitors": [], "output_type": "none", "repeats": 2 },
+                "processor": python,
+                "solution": { "file": solution },
+                "out_dir": dir.path().join("out"),
+                "security": null,
+                "stress": {
+                    "generator": { "processor": python, "source": { "file": generator } },
+                    "brute": { "processor": python, "source": { "file": brute } },
+                    "args": ["{size}", "{seed}"],
+                    "iterations": 3,
+                    "max_size": 3,
+                    "work_dir": dir.path().join("stress")
+                }
+            }))
+            .unwrap();
+
+        let runner = crate::checker::LocalRunner::new(dir.path().join("out"));
+        crate::acadchecker::AcadChecker::new().stress(config, runner.as_fn())
+    };
+
+    let correct = write("correct.py", "print(int(input()) * 2)\n");
+    assert!(matches!(
+        stress(correct),
+        crate::acadchecker::stress::Outcome::Passed { iterations: 3 }
+    ));
+
+    let wrong = write("wrong.py", "print(0)\n");
+    match stress(wrong) {
+        crate::acadchecker::stress::Outcome::Counterexample {
+            seed: 1, output, ..
+        } => {
+            assert!(matches!(output, crate::checker::Output::Repeated { .. }));
+            assert!(!output.passed());
+        }
+        o => panic!("{:?}", o),
+    }
+}
+
+#[test]
+fn discovered_tests_do_not_replace_configured_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["ins/1.in", "ins/2.in", "refs/1.ref", "refs/2.ref"] {
+        let path = dir.path().join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+
+    let config = |in_refs: serde_json::Value| -> crate::acadchecker::config::Config {
+        serde_json::from_value(serde_json::json!({
+            "checker": { "monitors": [], "output_type": "none", "in_refs": in_refs },
+            "processor": { "python": { "version": "3", "flags": [] } },
+            "solution": { "file": "sol.py" },
+            "out_dir": "out",
+            "security": null,
+            "discover": { "ins": dir.path().join("ins"), "refs": dir.path().join("refs") }
+        }))
+        .unwrap()
+    };
+
+    let tests = config(serde_json::json!({ "3": ["3.in", "3.ref"] }))
+        .extra_tests()
+        .unwrap();
+    assert_eq!(tests.keys().collect::<Vec<_>>(), vec![&1, &2]);
+
+    let error = config(serde_json::json!({ "2": ["2.in", "2.ref"] }))
+        .extra_tests()
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Test 2 is discovered but is already configured."
+    );
+}
+
+#[test]
+fn time_limit_is_adjusted_for_the_language() {
+    let config = |language: &str| -> crate::acadchecker::config::Config {
+        serde_json::from_value(serde_json::json!({
+            "checker": {
+                "monitors": [{ "time": { "limit": { "secs": 2, "nanos": 0 } } }],
+                "output_type": "none"
+            },
+            "processor": { "gcc": { "language": language, "flags": [], "exec": "sol" } },
+            "solution": { "file": "sol.c" },
+            "out_dir": "out",
+            "security": null,
+            "time_limits": {
+                "c++": { "offset": 1 },
+                "d": { "multiplier": 3, "offset": 0.5 }
+            }
+        }))
+        .unwrap()
+    };
+
+    assert_eq!(
+        config("c++").time_limit(),
+        Some(std::time::Duration::from_secs(3))
+    );
+    assert_eq!(
+        config("d").time_limit(),
+        Some(std::time::Duration::from_millis(6500))
+    );
+    assert_eq!(
+        config("c").time_limit(),
+        Some(std::time::Duration::from_secs(2))
+    );
+
+    match config("d").monitors().as_slice() {
+        [crate::checker::MonitorType::Timeout { limit }] => {
+            assert_eq!(*limit, std::time::Duration::from_millis(6500));
+        }
+        m => panic!("{:?}", m),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn solutions_run_inside_the_harness() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let write = |name: &str, contents: &str| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    };
+    let output = |command: Vec<std::ffi::OsString>| {
+        let output = std::process::Command::new(&command[0])
+            .args(&command[1..])
+            .arg("7")
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let processor = |processor: serde_json::Value| {
+        serde_json::from_value::<crate::acadchecker::config::SupportedProcessor>(processor).unwrap()
+    };
+
+    // Python solutions are imported, and a main block can't be renamed.
+    let harness = write(
+        "grader.py",
+        "import sys, solution\nprint(solution.double(int(sys.argv[1])))\n",
+    );
+    let python = |main: serde_json::Value| {
+        serde_json::from_value::<crate::acadchecker::config::SupportedProcessor>(
+            serde_json::json!({ "python": {
+                "version": "3",
+                "flags": [],
+                "harness": { "sources": [harness], "main": main }
+            } }),
+        )
+    };
+
+    let solution = write("solution.py", "def double(n):\n    return 2 * n\n");
+    let command = python(serde_json::json!("reject"))
+        .unwrap()
+        .command(crate::solution::Source::File(solution))
+        .unwrap();
+    assert_eq!(output(command), "14\n");
+
+    let solution = write(
+        "solution.py",
+        "def double(n):\n    return 2 * n\n\nif __name__ == '__main__':\n    print('main')\n",
+    );
+    assert!(matches!(
+        python(serde_json::json!("reject"))
+            .unwrap()
+            .command(crate::solution::Source::File(solution.clone())),
+        Err(crate::language::Error::MainDefined)
+    ));
+    let error = python(serde_json::json!({ "rename": "student_main" })).unwrap_err();
+    assert!(error.to_string().contains("can't be renamed"), "{}", error);
+
+    // C++ solutions are linked with the harness, with their main renamed.
+    std::fs::create_dir(dir.path().join("include")).unwrap();
+    let header = write("include/double.h", "int twice(int n);\n");
+    let harness = write(
+        "grader.cpp",
+        "#include <cstdio>\n#include <cstdlib>\n#include \"double.h\"\n\
+         int main(int argc, char **argv) { std::printf(\"%d\\n\", twice(std::atoi(argv[1]))); }\n",
+    );
+    let solution = write(
+        "solution.cpp",
+        "#include \"double.h\"\nint twice(int n) { return 2 * n; }\nint main() { return 1; }\n",
+    );
+    let gcc = |main: serde_json::Value| {
+        processor(serde_json::json!({ "gcc": {
+            "language": "c++",
+            "flags": [],
+            "exec": dir.path().join("solution"),
+            "harness": { "sources": [harness], "headers": [header], "main": main }
+        } }))
+    };
+
+    let command = gcc(serde_json::json!({ "rename": "student_main" }))
+        .command(crate::solution::Source::File(solution.clone()))
+        .unwrap();
+    assert_eq!(output(command), "14\n");
+    assert!(matches!(
+        gcc(serde_json::json!("reject")).command(crate::solution::Source::File(solution)),
+        Err(crate::language::Error::MainDefined)
+    ));
+
+    // Each file of a directory is compiled, and failures tell why.
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    write("src/twice.h", "int add(int a, int b);\n");
+    write(
+        "src/twice.cpp",
+        "#include \"double.h\"\n#include \"twice.h\"\nint twice(int n) { return add(n, n); }\n",
+    );
+    write("src/add.cpp", "int add(int a, int b) { return a + b; }\n");
+    let command = gcc(serde_json::json!("reject"))
+        .command(crate::solution::Source::Directory(dir.path().join("src")))
+        .unwrap();
+    assert_eq!(output(command), "14\n");
+
+    write("src/add.cpp", "int add(int a, int b) { return a + c; }\n");
+    match gcc(serde_json::json!("reject"))
+        .command(crate::solution::Source::Directory(dir.path().join("src")))
+    {
+        Err(crate::language::Error::CompilationFailed(message)) => {
+            assert!(message.contains("'c' was not declared"), "{}", message);
+        }
+        r => panic!("{:?}", r),
+    }
+}
+
+#[test]
+fn xunit_cases_are_scored() {
+    let xunit: crate::acadchecker::xunit::XUnit = serde_json::from_value(serde_json::json!({
+        "command": ["pytest", "--junitxml=report.xml"],
+        "report": "report.xml",
+        "tests": {
+            "1": { "name": "tests.test_list.test_push", "points": 3 },
+            "2": { "name": "test_pop" },
+            "3": { "name": "test_sort" },
+            "4": { "name": "test_missing" }
+        }
+    }))
+    .unwrap();
+
+    let report = r#"<?xml version="1.0" encoding="utf-8"?>
+<testsuites>
+  <testsuite name="pytest" tests="3" failures="1" skipped="1">
+    <testcase classname="tests.test_list" name="test_push" time="0.01" />
+    <testcase classname="tests.test_list" name="test_pop" time="0.02">
+      <failure message="assert 2 == 3">def test_pop():
+&gt;       assert pop() == 3</failure>
+    </testcase>
+    <testcase classname="tests.test_list" name="test_sort" time="0">
+      <skipped message="not implemented" />
+    </testcase>
+  </testsuite>
+</testsuites>"#;
+
+    let results = xunit
+        .results(report, &crate::checker::OutputType::Scored { per_test: 5 })
+        .unwrap();
+
+    assert!(matches!(
+        results[&1],
+        crate::checker::Output::Score {
+            score: 3,
+            message: None
+        }
+    ));
+    match &results[&2] {
+        crate::checker::Output::Score {
+            score: 0,
+            message: Some(message),
+        } => {
+            assert!(message.starts_with("test_pop: failure: assert 2 == 3\n"));
+            assert!(message.contains("assert pop() == 3"));
+        }
+        r => panic!("{:?}", r),
+    }
+    assert!(matches!(
+        &results[&3],
+        crate::checker::Output::Ignored { message: Some(m) } if m == "not implemented"
+    ));
+    assert!(matches!(
+        &results[&4],
+        crate::checker::Output::Score { score: 0, message: Some(m) } if m.contains("not found")
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn xunit_suite_runs_like_the_tests() {
+    let dir = tempfile::tempdir().unwrap();
+    let (work_dir, suite_dir) = (dir.path().join("work"), dir.path().join("suite"));
+    std::fs::create_dir(&work_dir).unwrap();
+
+    let xunit = |script: &str, report: &str| -> crate::acadchecker::xunit::XUnit {
+        serde_json::from_value(serde_json::json!({
+            "command": ["sh", "-c", script, "{report}"],
+            "work_dir": work_dir,
+            "report": report,
+            "time_limit": { "secs": 0, "nanos": 500_000_000 },
+            "tests": { "1": { "name": "test_push" } }
+        }))
+        .unwrap()
+    };
+    // The time limit of the tests doesn't apply to the suite.
+    let runner = crate::checker::LocalRunner::new(dir.path().join("out")).with_monitors(&[
+        crate::checker::MonitorType::Timeout {
+            limit: std::time::Duration::from_millis(1),
+        },
+    ]);
+    let run = |xunit: crate::acadchecker::xunit::XUnit| {
+        xunit.run(&runner, &suite_dir, &crate::checker::OutputType::None)
+    };
+
+    // The suite runs in its work_dir, and failing tests fail the command.
+    let script = "touch here; echo out; echo err >&2; \
+        echo '<testsuite><testcase name=\"test_push\" /></testsuite>' > \"$0\"; exit 1";
+    let results = run(xunit(script, "report.xml")).unwrap();
+    assert!(matches!(results[&1], crate::checker::Output::Passed));
+    assert!(work_dir.join("here").exists());
+    assert_eq!(
+        std::fs::read_to_string(suite_dir.join("0.out")).unwrap(),
+        "out\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(suite_dir.join("0.err")).unwrap(),
+        "err\n"
+    );
+
+    // Reports left in the directory are gone before the suite runs.
+    let error = run(xunit("true", "report.xml")).unwrap_err();
+    assert!(
+        error.to_string().starts_with("xUnit suite: report.xml: "),
+        "{}",
+        error
+    );
+
+    // A hanging suite is stopped at its time limit.
+    let error = run(xunit("sleep 10", "report.xml")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "xUnit suite: Running test failed: Time exceeded!"
+    );
+
+    // The report is a file of that directory.
+    assert!(run(xunit("true", "../report.xml")).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn results_keep_their_shape_without_a_time_limit() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let write = |name: &str, contents: &str| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    };
+    let (input, reference) = (write("1.in", "1\n"), write("1.ref", "1\n"));
+    let solution = write("sol.py", "print(input())\n");
+    std::fs::create_dir(dir.path().join("out")).unwrap();
+
+    let run = |monitors: serde_json::Value| {
+        let config: crate::acadchecker::config::Config =
+            serde_json::from_value(serde_json::json!({
+                "checker": {
+                    "monitors": monitors,
+                    "output_type": "none",
+                    "in_refs": { "1": [input, reference] }
+                },
+                "processor": { "python": { "version": "3", "flags": [] } },
+                "solution": { "file": solution },
+                "out_dir": dir.path().join("out"),
+                "security": null
+            }))
+            .unwrap();
+
+        let runner = config.runner().unwrap();
+        serde_json::to_value(crate::acadchecker::AcadChecker::new().run(config, runner.as_fn()))
+            .unwrap()
+    };
+
+    assert_eq!(
+        run(serde_json::json!([])),
+        serde_json::json!({ "results": { "1": "passed" } })
+    );
+    assert_eq!(
+        run(serde_json::json!([{ "time": { "limit": { "secs": 5, "nanos": 0 } } }])),
+        serde_json::json!({ "results_with_limit": {
+            "results": { "1": "passed" },
+            "time_limit": { "secs": 5, "nanos": 0 }
+        } })
+    );
+}
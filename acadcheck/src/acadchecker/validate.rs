@@ -0,0 +1,48 @@
+//! Validators of the test inputs of a problem.
+
+/// A validator program, compiled like the solution, or declarative
+/// constraints.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub enum Validator {
+    /// Reads an input from stdin and exits with a non-zero status if it is
+    /// invalid, with the reason on stderr.
+    #[serde(rename = "program")]
+    Program(crate::acadchecker::generate::Program),
+    #[serde(rename = "constraints")]
+    Constraints(crate::checker::Constraints),
+}
+
+impl Validator {
+    /// Checks every input and returns the reason each invalid one was
+    /// rejected for.
+    pub fn validate<'a, I>(
+        &self,
+        inputs: I,
+    ) -> Result<std::collections::BTreeMap<usize, String>, anyhow::Error>
+    where
+        I: IntoIterator<Item = (usize, &'a std::path::PathBuf)>,
+    {
+        let command = match self {
+            Validator::Program(program) => {
+                Some(crate::acadchecker::generate::compile("Validator", program)?)
+            }
+            Validator::Constraints(_) => None,
+        };
+
+        let mut invalid = std::collections::BTreeMap::new();
+
+        for (key, input) in inputs {
+            let checked = match (self, &command) {
+                (Validator::Constraints(constraints), _) => constraints.check(input),
+                (_, Some(command)) => crate::checker::check_with(command, input),
+                _ => Ok(()),
+            };
+
+            if let Err(reason) = checked {
+                invalid.insert(key, reason);
+            }
+        }
+
+        Ok(invalid)
+    }
+}
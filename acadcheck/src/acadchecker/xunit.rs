@@ -0,0 +1,178 @@
+//! Unit-test suites (JUnit, pytest, GoogleTest, ...) judged from their
+//! JUnit-XML reports, so their test cases count like in/ref tests.
+
+/// Characters of a failure shown in the result of a test case.
+const FAILURE_EXCERPT: usize = 1024;
+
+/// A unit-test suite and the test cases counted as tests.
+///
+/// The suite runs the solution, so it runs as the tests do, and it writes its
+/// report in a directory the checker creates for the run and takes back after
+/// it. Nothing can be planted there beforehand or changed afterwards, but the
+/// report is only as trustworthy as the suite: code it runs can write it too.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct XUnit {
+    /// Command that runs the suite, where `{report}` is replaced by the path of
+    /// the report. It may fail when tests fail.
+    pub command: Vec<String>,
+    /// Directory the command runs in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_dir: Option<std::path::PathBuf>,
+    /// File name of the JUnit-XML report written by the command.
+    pub report: String,
+    /// Time limit of the whole suite, instead of the one of each test.
+    #[serde(default = "default_time_limit")]
+    pub time_limit: std::time::Duration,
+    /// Test cases counted as tests, by test number.
+    #[serde(deserialize_with = "crate::util::format::test_keys")]
+    pub tests: std::collections::BTreeMap<usize, Case>,
+}
+
+/// A test case of the suite, named `name` or `classname.name` in the report.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Case {
+    pub name: String,
+    /// Points of the case. Defaults to the points per test of the checker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<usize>,
+}
+
+fn default_time_limit() -> std::time::Duration {
+    std::time::Duration::from_secs(60)
+}
+
+impl XUnit {
+    /// Runs the suite with the runner of the tests, so it runs as the same
+    /// user, and reads the result of every test case from its report. The
+    /// report, stdout (`0.out`) and stderr (`0.err`) of the suite are kept in
+    /// `dir`, which is created anew.
+    #[cfg(unix)]
+    pub fn run(
+        &self,
+        runner: &crate::checker::LocalRunner,
+        dir: &std::path::Path,
+        output_type: &crate::checker::OutputType,
+    ) -> Result<std::collections::BTreeMap<usize, crate::checker::Output>, anyhow::Error> {
+        if self.command.is_empty() {
+            return Err(anyhow::format_err!("Command of the xUnit suite is empty."));
+        }
+
+        let name = std::path::Path::new(&self.report);
+        if name.components().count() != 1 || name.file_name().is_none() {
+            return Err(anyhow::format_err!(
+                "Report of the xUnit suite must be a file name: {:?}",
+                self.report
+            ));
+        }
+
+        // The suite runs in its work_dir, so the report path is absolute.
+        let dir = std::env::current_dir()?.join(dir);
+        let report = dir.join(name);
+        let command = self
+            .command
+            .iter()
+            .map(|a| a.replace("{report}", &report.to_string_lossy()))
+            .collect::<Vec<_>>();
+
+        // The suite fails when its tests fail, which the report tells apart.
+        let run = runner.run_command(&command, self.work_dir.as_deref(), &dir, self.time_limit);
+        if let Err(e @ crate::checker::Error::TimeExceeded) = &run {
+            return Err(anyhow::format_err!("xUnit suite: {}", e));
+        }
+
+        let read = crate::checker::config::open_output(&report).and_then(|mut f| {
+            let mut contents = String::new();
+            std::io::Read::read_to_string(&mut f, &mut contents).map(|_| contents)
+        });
+        let contents = read.map_err(|e| match &run {
+            Ok(()) => anyhow::format_err!("xUnit suite: {}: {}", self.report, e),
+            Err(r) => anyhow::format_err!("xUnit suite: {}: {} ({})", self.report, e, r),
+        })?;
+
+        self.results(&contents, output_type)
+    }
+
+    /// Gives the verdict of every test case from a JUnit-XML report. Cases
+    /// missing from the report fail.
+    pub fn results(
+        &self,
+        report: &str,
+        output_type: &crate::checker::OutputType,
+    ) -> Result<std::collections::BTreeMap<usize, crate::checker::Output>, anyhow::Error> {
+        let document = roxmltree::Document::parse(report)
+            .map_err(|e| anyhow::format_err!("xUnit report {:?}: {}", self.report, e))?;
+
+        let cases = document
+            .descendants()
+            .filter(|n| n.has_tag_name("testcase"))
+            .collect::<Vec<_>>();
+
+        let per_test = match output_type {
+            crate::checker::OutputType::Scored { per_test } => Some(*per_test),
+            _ => None,
+        };
+
+        let mut results = std::collections::BTreeMap::new();
+
+        for (key, case) in &self.tests {
+            let node = cases.iter().find(|n| {
+                let name = n.attribute("name").unwrap_or_default();
+                let full = format!("{}.{}", n.attribute("classname").unwrap_or_default(), name);
+                case.name == name || case.name == full
+            });
+
+            let failure = match node {
+                None => Some(format!("{}: not found in the report.", case.name)),
+                Some(node) => {
+                    if let Some(skipped) = node.children().find(|c| c.has_tag_name("skipped")) {
+                        let message = skipped.attribute("message").map(String::from);
+                        results.insert(*key, crate::checker::Output::Ignored { message });
+                        continue;
+                    }
+
+                    node.children()
+                        .find(|c| c.has_tag_name("failure") || c.has_tag_name("error"))
+                        .map(|c| failure_message(&case.name, c))
+                }
+            };
+
+            let output = match (failure, per_test) {
+                (None, Some(per_test)) => crate::checker::Output::Score {
+                    score: case.points.unwrap_or(per_test),
+                    message: None,
+                },
+                (None, None) => crate::checker::Output::Passed,
+                (Some(message), Some(_)) => crate::checker::Output::Score {
+                    score: 0,
+                    message: Some(message),
+                },
+                (Some(message), None) => crate::checker::Output::Failed(message),
+            };
+
+            results.insert(*key, output);
+        }
+
+        Ok(results)
+    }
+}
+
+/// Message of a `failure` or `error` element, followed by the beginning of
+/// its text (usually the traceback).
+fn failure_message(name: &str, node: roxmltree::Node) -> String {
+    let mut message = format!("{}: {}", name, node.tag_name().name());
+
+    if let Some(m) = node.attribute("message") {
+        message.push_str(&format!(": {}", m));
+    }
+
+    let text = node.text().unwrap_or_default().trim();
+    if !text.is_empty() {
+        message.push('\n');
+        message.extend(text.chars().take(FAILURE_EXCERPT));
+        if text.chars().count() > FAILURE_EXCERPT {
+            message.push_str("\n[truncated]");
+        }
+    }
+
+    message
+}
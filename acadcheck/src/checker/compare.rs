@@ -0,0 +1,383 @@
+//! Streaming comparison of outputs, with bounded memory.
+
+/// Bytes of context kept around the first difference.
+pub(crate) const CONTEXT: usize = 64;
+
+/// Prefix of the reference lines that are regexes, in
+/// [`Patterns`](crate::checker::Comparison::Patterns) mode.
+pub(crate) const PATTERN_PREFIX: &str = "re:";
+
+/// The first difference between two streams, as excerpts of the line it is
+/// on.
+#[derive(Debug)]
+pub(crate) struct Mismatch {
+    /// Number of equal bytes before the difference.
+    pub(crate) offset: u64,
+    pub(crate) line: usize,
+    pub(crate) this: String,
+    pub(crate) other: String,
+}
+
+/// Compares two streams byte by byte, chunk by chunk, and stops at the first
+/// difference. Only the current line (up to [`CONTEXT`] bytes) is kept in
+/// memory.
+pub(crate) fn first_mismatch<A, B>(mut this: A, mut other: B) -> std::io::Result<Option<Mismatch>>
+where
+    A: std::io::BufRead,
+    B: std::io::BufRead,
+{
+    let mut line = 1;
+    let mut offset = 0;
+
+    // Tail of the current line, which is the same in both streams.
+    let mut prefix = Vec::with_capacity(CONTEXT);
+    let mut cut = false;
+
+    loop {
+        let (a, b) = (this.fill_buf()?, other.fill_buf()?);
+
+        if a.is_empty() && b.is_empty() {
+            return Ok(None);
+        }
+
+        let n = a.len().min(b.len());
+        let same = a[..n]
+            .iter()
+            .zip(&b[..n])
+            .position(|(x, y)| x != y)
+            .unwrap_or(n);
+
+        let equal = &a[..same];
+        match equal.iter().rposition(|c| *c == b'\n') {
+            Some(last) => {
+                line += equal.iter().filter(|c| **c == b'\n').count();
+                prefix.clear();
+                prefix.extend_from_slice(&equal[last + 1..]);
+            }
+            None => prefix.extend_from_slice(equal),
+        }
+        if prefix.len() > CONTEXT {
+            prefix.drain(..prefix.len() - CONTEXT);
+            cut = true;
+        }
+
+        // Either a byte differs or one of the streams ended.
+        let found = same < n || n == 0;
+
+        this.consume(same);
+        other.consume(same);
+        offset += same as u64;
+
+        if found {
+            let prefix = format!(
+                "{}{}",
+                if cut { "..." } else { "" },
+                String::from_utf8_lossy(&prefix)
+            );
+
+            return Ok(Some(Mismatch {
+                offset,
+                line,
+                this: format!("line {}: {}{}", line, prefix, rest_of_line(&mut this)?),
+                other: format!("line {}: {}{}", line, prefix, rest_of_line(&mut other)?),
+            }));
+        }
+    }
+}
+
+/// Reads the rest of the line, up to [`CONTEXT`] bytes.
+fn rest_of_line<R>(reader: &mut R) -> std::io::Result<String>
+where
+    R: std::io::BufRead,
+{
+    let mut rest = Vec::with_capacity(CONTEXT);
+
+    loop {
+        let buf = reader.fill_buf()?;
+
+        if buf.is_empty() {
+            return Ok(format!("{}[EOF]", String::from_utf8_lossy(&rest)));
+        }
+
+        let take = buf.len().min(CONTEXT - rest.len());
+        match buf[..take].iter().position(|c| *c == b'\n') {
+            Some(end) => {
+                rest.extend_from_slice(&buf[..end]);
+                return Ok(String::from_utf8_lossy(&rest).into_owned());
+            }
+            None => {
+                rest.extend_from_slice(&buf[..take]);
+                reader.consume(take);
+            }
+        }
+
+        if rest.len() == CONTEXT {
+            return Ok(format!("{}...", String::from_utf8_lossy(&rest)));
+        }
+    }
+}
+
+/// Compares two streams under a [`Comparison`](crate::checker::Comparison)
+/// mode, and returns the first difference. The unordered modes only keep the
+/// distinct lines (or tokens, or the current block) in memory, while JSON and
+/// CSV are read whole.
+pub(crate) fn mismatch_as<A, B>(
+    mut this: A,
+    mut other: B,
+    comparison: &crate::checker::Comparison,
+) -> std::io::Result<Option<Mismatch>>
+where
+    A: std::io::BufRead,
+    B: std::io::BufRead,
+{
+    match comparison {
+        crate::checker::Comparison::Lines => multiset_mismatch(this, other, false),
+        crate::checker::Comparison::Tokens => multiset_mismatch(this, other, true),
+        crate::checker::Comparison::SortedBlocks => {
+            let mut block = 1;
+
+            loop {
+                let (mut a, mut b) = (read_block(&mut this)?, read_block(&mut other)?);
+
+                if a.is_empty() && b.is_empty() {
+                    return Ok(None);
+                }
+
+                a.sort();
+                b.sort();
+
+                // First differing line, or the end of the shorter block.
+                let i = a
+                    .iter()
+                    .zip(&b)
+                    .position(|(x, y)| x != y)
+                    .unwrap_or(a.len().min(b.len()));
+
+                if a.len() != b.len() || i < a.len() {
+                    let side = |lines: &Vec<Vec<u8>>| match lines.get(i) {
+                        Some(l) => format!("block {}: {}", block, excerpt(l)),
+                        None => format!("block {}: [EOF]", block),
+                    };
+
+                    return Ok(Some(Mismatch {
+                        offset: 0,
+                        line: 0,
+                        this: side(&a),
+                        other: side(&b),
+                    }));
+                }
+
+                block += 1;
+            }
+        }
+        crate::checker::Comparison::Patterns => {
+            let mut line = 1;
+
+            loop {
+                let (a, b) = (read_line(&mut this)?, read_line(&mut other)?);
+
+                let matched = match (&a, &b) {
+                    (None, None) => {
+                        return Ok(None);
+                    }
+                    (Some(a), Some(b)) => match b.strip_prefix(PATTERN_PREFIX.as_bytes()) {
+                        Some(pattern) => {
+                            let pattern = String::from_utf8_lossy(pattern);
+                            match regex::Regex::new(&format!("^(?:{})$", pattern)) {
+                                Ok(r) => r.is_match(&String::from_utf8_lossy(a)),
+                                Err(e) => {
+                                    return Ok(Some(Mismatch {
+                                        offset: 0,
+                                        line,
+                                        this: format!("line {}: {}", line, excerpt(a)),
+                                        other: format!("line {}: invalid regex: {}", line, e),
+                                    }));
+                                }
+                            }
+                        }
+                        None => a == b,
+                    },
+                    _ => false,
+                };
+
+                if !matched {
+                    let side = |l: &Option<Vec<u8>>| match l {
+                        Some(l) => format!("line {}: {}", line, excerpt(l)),
+                        None => format!("line {}: [EOF]", line),
+                    };
+
+                    return Ok(Some(Mismatch {
+                        offset: 0,
+                        line,
+                        this: side(&a),
+                        other: side(&b),
+                    }));
+                }
+
+                line += 1;
+            }
+        }
+        crate::checker::Comparison::Regex => {
+            let (a, b) = (read_all(this)?, read_all(other)?);
+            let pattern = String::from_utf8_lossy(&b);
+
+            let matched = match regex::Regex::new(&format!(r"\A(?:{})\z", pattern)) {
+                Ok(r) => r.is_match(&String::from_utf8_lossy(&a)),
+                Err(e) => {
+                    return Ok(Some(Mismatch {
+                        offset: 0,
+                        line: 0,
+                        this: excerpt(&a),
+                        other: format!("invalid regex: {}", e),
+                    }));
+                }
+            };
+
+            if matched {
+                Ok(None)
+            } else {
+                Ok(Some(Mismatch {
+                    offset: 0,
+                    line: 0,
+                    this: excerpt(&a),
+                    other: format!("regex: {}", excerpt(&b)),
+                }))
+            }
+        }
+        crate::checker::Comparison::Json {
+            tolerance,
+            unordered_arrays,
+        } => {
+            let (a, b) = (read_all(this)?, read_all(other)?);
+            Ok(crate::checker::structured::json_mismatch(
+                &a,
+                &b,
+                *tolerance,
+                *unordered_arrays,
+            ))
+        }
+        crate::checker::Comparison::Csv {
+            header,
+            unordered_rows,
+            tolerance,
+        } => {
+            let (a, b) = (read_all(this)?, read_all(other)?);
+            Ok(crate::checker::structured::csv_mismatch(
+                &a,
+                &b,
+                *header,
+                *unordered_rows,
+                *tolerance,
+            ))
+        }
+        _ => first_mismatch(this, other),
+    }
+}
+
+/// Reads a line without its newline, or `None` at the end.
+fn read_line<R>(reader: &mut R) -> std::io::Result<Option<Vec<u8>>>
+where
+    R: std::io::BufRead,
+{
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+    Ok(Some(line))
+}
+
+fn read_all<R>(mut reader: R) -> std::io::Result<Vec<u8>>
+where
+    R: std::io::BufRead,
+{
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+/// Compares the lines (or the whitespace-separated tokens) of two streams as
+/// multisets.
+fn multiset_mismatch<A, B>(this: A, other: B, tokens: bool) -> std::io::Result<Option<Mismatch>>
+where
+    A: std::io::BufRead,
+    B: std::io::BufRead,
+{
+    // How many times each item appears in each stream.
+    let mut counts = std::collections::BTreeMap::<Vec<u8>, (usize, usize)>::new();
+
+    count(this, tokens, &mut counts, |c| c.0 += 1)?;
+    count(other, tokens, &mut counts, |c| c.1 += 1)?;
+
+    Ok(counts
+        .into_iter()
+        .find(|(_, (a, b))| a != b)
+        .map(|(item, (a, b))| Mismatch {
+            offset: 0,
+            line: 0,
+            this: format!("{} x {}", a, excerpt(&item)),
+            other: format!("{} x {}", b, excerpt(&item)),
+        }))
+}
+
+fn count<R, F>(
+    reader: R,
+    tokens: bool,
+    counts: &mut std::collections::BTreeMap<Vec<u8>, (usize, usize)>,
+    increment: F,
+) -> std::io::Result<()>
+where
+    R: std::io::BufRead,
+    F: Fn(&mut (usize, usize)),
+{
+    for line in reader.split(b'\n') {
+        let line = line?;
+
+        if tokens {
+            for token in line
+                .split(|c| c.is_ascii_whitespace())
+                .filter(|t| !t.is_empty())
+            {
+                increment(counts.entry(Vec::from(token)).or_default());
+            }
+        } else {
+            increment(counts.entry(line).or_default());
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the lines up to the next blank line. Consecutive blank lines are a
+/// single separator.
+fn read_block<R>(reader: &mut R) -> std::io::Result<Vec<Vec<u8>>>
+where
+    R: std::io::BufRead,
+{
+    let mut lines = Vec::new();
+
+    while let Some(line) = read_line(reader)? {
+        if line.is_empty() {
+            if lines.is_empty() {
+                continue;
+            }
+            return Ok(lines);
+        }
+
+        lines.push(line);
+    }
+
+    Ok(lines)
+}
+
+/// Up to [`CONTEXT`] bytes of a line, with invalid UTF-8 replaced.
+fn excerpt(line: &[u8]) -> String {
+    if line.len() > CONTEXT {
+        format!("{}...", String::from_utf8_lossy(&line[..CONTEXT]))
+    } else {
+        String::from_utf8_lossy(line).into_owned()
+    }
+}
@@ -1,7 +1,6 @@
 //! Defines the configuration of a checker.
 
-use std::io::{Read, Seek};
-#[derive(std::fmt::Debug)]
+#[derive(std::fmt::Debug, Clone)]
 #[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CheckerConfig<I, O, T>
 where
@@ -11,7 +10,98 @@ where
 {
     pub monitors: T,
     pub output_type: OutputType,
+    #[cfg_attr(
+        feature = "use-serde",
+        serde(
+            default,
+            deserialize_with = "crate::util::format::test_keys",
+            bound(deserialize = "I: serde::Deserialize<'de>, O: serde::Deserialize<'de>")
+        )
+    )]
     pub in_refs: std::collections::BTreeMap<usize, (I, O)>,
+    /// Other accepted references of a test, besides the one in `in_refs`. The
+    /// test passes if its output matches any of them.
+    #[cfg_attr(
+        feature = "use-serde",
+        serde(
+            default,
+            deserialize_with = "crate::util::format::test_keys",
+            bound(deserialize = "O: serde::Deserialize<'de>")
+        )
+    )]
+    pub alternatives: std::collections::BTreeMap<usize, Vec<O>>,
+    /// How outputs are compared with their references.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub comparison: Comparison,
+    /// Comparison of specific tests, instead of `comparison`.
+    #[cfg_attr(
+        feature = "use-serde",
+        serde(default, deserialize_with = "crate::util::format::test_keys")
+    )]
+    pub per_test_comparison: std::collections::BTreeMap<usize, Comparison>,
+    /// Number of tests that run at the same time. Use 1 for strict timing.
+    #[cfg_attr(feature = "use-serde", serde(default = "default_workers"))]
+    pub workers: usize,
+    /// Pins each worker (and the tests it runs) to its own CPU. Only
+    /// available on Linux, ignored otherwise.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub pin_cpus: bool,
+    /// Decides which tests still run after a failure.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub policy: ExecutionPolicy,
+    /// Number of times each test runs, to find unstable solutions.
+    #[cfg_attr(feature = "use-serde", serde(default = "default_repeats"))]
+    pub repeats: usize,
+    /// Decides the verdict of a test whose runs disagree.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub decision: Decision,
+    /// Arguments, environment variables and stdin of specific tests, added to
+    /// the command by the runner.
+    #[cfg_attr(
+        feature = "use-serde",
+        serde(default, deserialize_with = "crate::util::format::test_keys")
+    )]
+    pub invocations: std::collections::BTreeMap<usize, Invocation>,
+    /// Where the tests read their input and write their outputs.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub io: IoMode,
+}
+
+#[cfg(feature = "use-serde")]
+fn default_workers() -> usize {
+    1
+}
+
+#[cfg(feature = "use-serde")]
+fn default_repeats() -> usize {
+    1
+}
+
+/// Configuration without tests or monitors, running each test once on a
+/// single worker.
+impl<I, O, T> Default for CheckerConfig<I, O, T>
+where
+    I: std::fmt::Debug + Eq + std::hash::Hash,
+    O: std::fmt::Debug + crate::checker::config::PartialEq<O> + std::cmp::PartialEq,
+    T: IntoIterator<Item = MonitorType> + Default,
+{
+    fn default() -> Self {
+        Self {
+            monitors: T::default(),
+            output_type: OutputType::default(),
+            in_refs: std::collections::BTreeMap::new(),
+            alternatives: std::collections::BTreeMap::new(),
+            comparison: Comparison::default(),
+            per_test_comparison: std::collections::BTreeMap::new(),
+            workers: 1,
+            pin_cpus: false,
+            policy: ExecutionPolicy::default(),
+            repeats: 1,
+            decision: Decision::default(),
+            invocations: std::collections::BTreeMap::new(),
+            io: IoMode::default(),
+        }
+    }
 }
 
 impl<I, O, T> CheckerConfig<I, O, T>
@@ -20,19 +110,37 @@ where
     O: std::fmt::Debug + crate::checker::config::PartialEq<O> + std::cmp::PartialEq,
     T: IntoIterator<Item = MonitorType>,
 {
+    /// Reads the configuration from a JSON file.
+    #[cfg(feature = "use-serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "use-serde")))]
+    pub fn from_json_file<P>(path: P) -> Result<Self, anyhow::Error>
+    where
+        P: AsRef<std::path::Path>,
+        Self: serde::de::DeserializeOwned,
+    {
+        crate::util::format::from_file(path.as_ref(), crate::util::format::Format::Json)
+    }
+
+    /// Reads the configuration from a JSON, TOML or YAML file, chosen by its
+    /// extension (`.json`, `.toml`, `.yaml` or `.yml`). Files without an
+    /// extension are read as JSON. Errors point to the
+    /// field and line that could not be read.
     #[cfg(feature = "use-serde")]
-    pub(crate) fn from_json_file<P>(_path: P) -> Result<Self, anyhow::Error>
+    #[cfg_attr(docsrs, doc(cfg(feature = "use-serde")))]
+    pub fn from_file<P>(path: P) -> Result<Self, anyhow::Error>
     where
         P: AsRef<std::path::Path>,
+        Self: serde::de::DeserializeOwned,
     {
-        todo!()
+        let format = crate::util::format::Format::from_path(path.as_ref())?;
+        crate::util::format::from_file(path.as_ref(), format)
     }
 }
 
 /// Monitor enums for different types of monitors that could be used to analyze
 /// the solution running.
 #[non_exhaustive]
-#[derive(std::fmt::Debug)]
+#[derive(std::fmt::Debug, Clone)]
 #[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MonitorType {
     /// Stops the execution of the solution on limit reached.
@@ -44,16 +152,177 @@ pub enum MonitorType {
 
 /// Output types
 #[non_exhaustive]
-#[derive(std::fmt::Debug)]
+#[derive(std::fmt::Debug, Clone, Default)]
 #[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OutputType {
     /// Monitor as a command for which the argument will be the executable.
     #[cfg_attr(feature = "use-serde", serde(rename = "scored"))]
     Scored { per_test: usize },
+    #[default]
     #[cfg_attr(feature = "use-serde", serde(rename = "none"))]
     None,
 }
 
+/// Execution policies, deciding whether the remaining tests should run after a
+/// test fails.
+#[non_exhaustive]
+#[derive(std::fmt::Debug, Clone, Default)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExecutionPolicy {
+    /// Runs every test.
+    #[default]
+    #[cfg_attr(feature = "use-serde", serde(rename = "all"))]
+    RunAll,
+    /// Skips all tests after the first failure.
+    #[cfg_attr(feature = "use-serde", serde(rename = "first_failure"))]
+    FirstFailure,
+    /// Skips the rest of a group (subtask) after its first failure, while the
+    /// other groups continue. Tests that are not in any group always run.
+    #[cfg_attr(feature = "use-serde", serde(rename = "group_first_failure"))]
+    GroupFirstFailure { groups: Vec<Vec<usize>> },
+}
+
+/// Comparison modes of an output and a reference.
+#[non_exhaustive]
+#[derive(std::fmt::Debug, Clone, Copy, Default, std::cmp::PartialEq)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Comparison {
+    /// Byte-exact comparison.
+    #[default]
+    #[cfg_attr(feature = "use-serde", serde(rename = "exact"))]
+    Exact,
+    /// Same lines, in any order.
+    #[cfg_attr(feature = "use-serde", serde(rename = "lines"))]
+    Lines,
+    /// Same whitespace-separated tokens, in any order.
+    #[cfg_attr(feature = "use-serde", serde(rename = "tokens"))]
+    Tokens,
+    /// Same blocks, separated by blank lines, in the same order, with the lines
+    /// of each block in any order.
+    #[cfg_attr(feature = "use-serde", serde(rename = "sorted_blocks"))]
+    SortedBlocks,
+    /// Each line of the reference is either a literal or, prefixed with
+    /// `re:`, a regex that the whole output line must match.
+    #[cfg_attr(feature = "use-serde", serde(rename = "patterns"))]
+    Patterns,
+    /// The whole reference is a regex that the whole output must match.
+    #[cfg_attr(feature = "use-serde", serde(rename = "regex"))]
+    Regex,
+    /// Same JSON values, whatever the key order and formatting. Numbers are
+    /// equal if they differ by at most `tolerance`, and arrays may be in any
+    /// order with `unordered_arrays`.
+    #[cfg_attr(feature = "use-serde", serde(rename = "json"))]
+    Json {
+        #[cfg_attr(feature = "use-serde", serde(default))]
+        tolerance: f64,
+        #[cfg_attr(feature = "use-serde", serde(default))]
+        unordered_arrays: bool,
+    },
+    /// Same CSV cells, whatever the whitespace around them. Numbers are equal
+    /// if they differ by at most `tolerance`. With `header`, the columns are
+    /// matched by the names in the first row, and with `unordered_rows`, the
+    /// rows may be in any order.
+    #[cfg_attr(feature = "use-serde", serde(rename = "csv"))]
+    Csv {
+        #[cfg_attr(feature = "use-serde", serde(default))]
+        header: bool,
+        #[cfg_attr(feature = "use-serde", serde(default))]
+        unordered_rows: bool,
+        #[cfg_attr(feature = "use-serde", serde(default))]
+        tolerance: f64,
+    },
+    /// Same directory trees: the same files, with the same contents and, if
+    /// `permissions` is set, the same permissions. Outputs and references
+    /// that are files are compared byte by byte.
+    #[cfg_attr(feature = "use-serde", serde(rename = "tree"))]
+    Tree {
+        #[cfg_attr(feature = "use-serde", serde(default))]
+        permissions: bool,
+    },
+}
+
+/// Verdict of a test whose repeated runs disagree.
+#[non_exhaustive]
+#[derive(std::fmt::Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Decision {
+    /// The test fails if any run failed.
+    #[default]
+    #[cfg_attr(feature = "use-serde", serde(rename = "worst"))]
+    Worst,
+    /// The test gets the verdict of most runs, failing on a tie.
+    #[cfg_attr(feature = "use-serde", serde(rename = "majority"))]
+    Majority,
+}
+
+/// Where the tests read their input and write their outputs.
+#[non_exhaustive]
+#[derive(std::fmt::Debug, Clone, Default)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IoMode {
+    /// The input is stdin and the output is stdout.
+    #[default]
+    #[cfg_attr(feature = "use-serde", serde(rename = "standard"))]
+    Standard,
+    /// Each test runs in a fresh directory, with its input copied there as
+    /// `input`. Its outputs are the files named in `outputs`, each compared
+    /// with the file of the same name in the reference directory, or with the
+    /// reference itself if there is only one.
+    #[cfg_attr(feature = "use-serde", serde(rename = "files"))]
+    Files { input: String, outputs: Vec<String> },
+}
+
+/// How a test is invoked, besides the command of the solution.
+#[derive(std::fmt::Debug, Clone)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Invocation {
+    /// Arguments added after the command. `{input}` is replaced by the path of
+    /// the input file.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub args: Vec<String>,
+    /// Environment variables of the test.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub env: std::collections::BTreeMap<String, String>,
+    /// Whether the input is given as stdin. Defaults to true.
+    #[cfg_attr(feature = "use-serde", serde(default = "default_stdin"))]
+    pub stdin: bool,
+    /// Directory whose contents seed the working directory of the test, with
+    /// file-based [`IoMode::Files`](crate::checker::IoMode::Files).
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub fixture: Option<std::path::PathBuf>,
+}
+
+#[cfg(feature = "use-serde")]
+fn default_stdin() -> bool {
+    true
+}
+
+/// Invocation that only gives the input as stdin.
+impl Default for Invocation {
+    fn default() -> Self {
+        Self {
+            args: Vec::new(),
+            env: std::collections::BTreeMap::new(),
+            stdin: true,
+            fixture: None,
+        }
+    }
+}
+
+impl ExecutionPolicy {
+    /// The group a test belongs to under this policy, if any.
+    pub(crate) fn group_of(&self, key: usize) -> Option<usize> {
+        match self {
+            ExecutionPolicy::RunAll => None,
+            ExecutionPolicy::FirstFailure => Some(0),
+            ExecutionPolicy::GroupFirstFailure { groups } => {
+                groups.iter().position(|g| g.contains(&key))
+            }
+            _ => None,
+        }
+    }
+}
+
 /// Trait for equality comparisons.
 /// x.ceq(y) can __not__ be written x == y.
 pub trait PartialEq<Rhs = Self>
@@ -64,8 +333,53 @@ where
     /// self_inner and other_inner should be the string representations of the
     /// values that are being compared, needed for test output messages.
     fn ceq(&self, other: &Rhs, self_inner: &mut String, other_inner: &mut String) -> bool;
+
+    /// Equality comparison between self and other under a
+    /// [`Comparison`](crate::checker::Comparison) mode. Defaults to
+    /// [`ceq`](crate::checker::PartialEq::ceq), whatever the mode.
+    fn ceq_as(
+        &self,
+        other: &Rhs,
+        comparison: &Comparison,
+        self_inner: &mut String,
+        other_inner: &mut String,
+    ) -> bool {
+        let _ = comparison;
+        self.ceq(other, self_inner, other_inner)
+    }
+
+    /// Equality comparison of the files named `names` in self and other, as
+    /// directories, for tests with file-based
+    /// [`IoMode::Files`](crate::checker::IoMode::Files). Only paths can be
+    /// compared this way.
+    fn ceq_files(
+        &self,
+        other: &Rhs,
+        names: &[String],
+        comparison: &Comparison,
+        self_inner: &mut String,
+        other_inner: &mut String,
+    ) -> bool {
+        let _ = (other, names, comparison, other_inner);
+        self_inner.push_str("Only files can be compared by name.");
+        false
+    }
+
+    /// How much of self matches other, used to name the closest reference of
+    /// a test that has several. Defaults to 0.
+    fn closeness(&self, _other: &Rhs) -> u64 {
+        0
+    }
+
+    /// Name of self in the messages of a test, such as the path of a file.
+    /// Values kept in memory have none.
+    fn name(&self) -> Option<String> {
+        None
+    }
 }
 
+/// Byte-exact comparison of the files, streamed chunk by chunk. On failure,
+/// self_inner and other_inner are excerpts of the first line that differs.
 impl PartialEq<std::path::PathBuf> for std::path::PathBuf {
     fn ceq(
         &self,
@@ -73,39 +387,281 @@ impl PartialEq<std::path::PathBuf> for std::path::PathBuf {
         self_inner: &mut String,
         other_inner: &mut String,
     ) -> bool {
-        let mut f = match std::fs::File::open(self) {
-            Ok(o) => o,
-            Err(_) => {
-                return false;
-            }
+        let Some((f, g)) = open_pair(self, other, self_inner, other_inner) else {
+            return false;
         };
 
-        let mut g = match std::fs::File::open(other) {
-            Ok(o) => o,
-            Err(_) => {
-                return false;
+        let mismatch = crate::checker::compare::first_mismatch(
+            std::io::BufReader::new(f),
+            std::io::BufReader::new(g),
+        );
+
+        report(mismatch, self_inner, other_inner)
+    }
+
+    fn ceq_as(
+        &self,
+        other: &std::path::PathBuf,
+        comparison: &Comparison,
+        self_inner: &mut String,
+        other_inner: &mut String,
+    ) -> bool {
+        if let Comparison::Tree { permissions } = comparison {
+            let is_dir = matches!(std::fs::symlink_metadata(self), Ok(m) if m.is_dir());
+            if is_dir && other.is_dir() {
+                let mismatch = crate::checker::tree::tree_mismatch(self, other, *permissions);
+                return report(mismatch, self_inner, other_inner);
             }
+        }
+
+        let Some((f, g)) = open_pair(self, other, self_inner, other_inner) else {
+            return false;
         };
 
-        let val = file_diff::diff_files(&mut f, &mut g);
+        let mismatch = crate::checker::compare::mismatch_as(
+            std::io::BufReader::new(f),
+            std::io::BufReader::new(g),
+            comparison,
+        );
 
-        f.rewind().unwrap();
-        g.rewind().unwrap();
+        report(mismatch, self_inner, other_inner)
+    }
 
-        match g.read_to_string(other_inner) {
-            Ok(_) => {}
-            Err(_) => {
+    /// If other is a file rather than a directory, it is the reference of the
+    /// only name. The first file that differs is reported, prefixed by its
+    /// name. Outputs that are symlinks, or reached through one, fail.
+    fn ceq_files(
+        &self,
+        other: &std::path::PathBuf,
+        names: &[String],
+        comparison: &Comparison,
+        self_inner: &mut String,
+        other_inner: &mut String,
+    ) -> bool {
+        for name in names {
+            let (output, reference) = match other.is_dir() {
+                true => (self.join(name), other.join(name)),
+                false if names.len() == 1 => (self.join(name), other.clone()),
+                false => {
+                    other_inner.push_str(&format!("{:?} is not a directory", other));
+                    return false;
+                }
+            };
+
+            match entry_metadata(self, name) {
+                Ok(m) if m.is_file() || m.is_dir() => {}
+                Ok(_) => {
+                    self_inner.push_str(&format!("{}: [not a regular file]", name));
+                    return false;
+                }
+                Err(_) => {
+                    self_inner.push_str(&format!("{}: [missing]", name));
+                    return false;
+                }
+            }
+            if !reference.exists() {
+                other_inner.push_str(&format!("{}: [missing]", name));
                 return false;
             }
-        }
 
-        match f.read_to_string(self_inner) {
-            Ok(_) => {}
-            Err(_) => {
+            let (mut this, mut that) = (String::new(), String::new());
+            if !output.ceq_as(&reference, comparison, &mut this, &mut that) {
+                self_inner.push_str(&format!("{}: {}", name, this));
+                other_inner.push_str(&format!("{}: {}", name, that));
                 return false;
             }
         }
 
-        val
+        true
+    }
+
+    fn closeness(&self, other: &std::path::PathBuf) -> u64 {
+        let (f, g) = match (open_output(self), std::fs::File::open(other)) {
+            (Ok(f), Ok(g)) => (f, g),
+            _ => {
+                return 0;
+            }
+        };
+
+        match crate::checker::compare::first_mismatch(
+            std::io::BufReader::new(f),
+            std::io::BufReader::new(g),
+        ) {
+            Ok(Some(m)) => m.offset,
+            _ => 0,
+        }
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.display().to_string())
+    }
+}
+
+/// Opens an output written by a test. Symlinks (which the test could point to
+/// files it can't read) and anything else than a regular file are refused.
+pub(crate) fn open_output(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        // Opening a FIFO would block until someone writes to it.
+        options.custom_flags(nix::libc::O_NOFOLLOW | nix::libc::O_NONBLOCK);
+    }
+
+    let file = options.open(path)?;
+    if !file.metadata()?.is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{:?} is not a regular file", path),
+        ));
+    }
+
+    Ok(file)
+}
+
+/// Opens the output of a test with [`open_output`] and its reference. If
+/// either can't be opened, the reason is its excerpt.
+fn open_pair(
+    output: &std::path::Path,
+    reference: &std::path::Path,
+    self_inner: &mut String,
+    other_inner: &mut String,
+) -> Option<(std::fs::File, std::fs::File)> {
+    let reason = |path: &std::path::Path, e: std::io::Error| match std::fs::symlink_metadata(path) {
+        Err(_) => String::from("[missing]"),
+        Ok(m) if !m.is_file() => String::from("[not a regular file]"),
+        Ok(_) => format!("[{}]", e),
+    };
+
+    match (open_output(output), std::fs::File::open(reference)) {
+        (Ok(f), Ok(g)) => Some((f, g)),
+        (f, g) => {
+            if let Err(e) = f {
+                self_inner.push_str(&reason(output, e));
+            }
+            if let Err(e) = g {
+                other_inner.push_str(&reason(reference, e));
+            }
+            None
+        }
+    }
+}
+
+/// Metadata of `root.join(name)`, without following symlinks. If a directory
+/// on the way is a symlink, it is the metadata of that symlink.
+fn entry_metadata(root: &std::path::Path, name: &str) -> std::io::Result<std::fs::Metadata> {
+    let mut path = root.to_path_buf();
+    let mut metadata = std::fs::symlink_metadata(&path)?;
+
+    for component in std::path::Path::new(name).components() {
+        if metadata.file_type().is_symlink() {
+            break;
+        }
+        path.push(component);
+        metadata = std::fs::symlink_metadata(&path)?;
+    }
+
+    Ok(metadata)
+}
+
+impl PartialEq<String> for String {
+    fn ceq(&self, other: &String, self_inner: &mut String, other_inner: &mut String) -> bool {
+        ceq_bytes(self.as_bytes(), other.as_bytes(), self_inner, other_inner)
+    }
+
+    fn ceq_as(
+        &self,
+        other: &String,
+        comparison: &Comparison,
+        self_inner: &mut String,
+        other_inner: &mut String,
+    ) -> bool {
+        let mismatch =
+            crate::checker::compare::mismatch_as(self.as_bytes(), other.as_bytes(), comparison);
+        report(mismatch, self_inner, other_inner)
+    }
+
+    fn closeness(&self, other: &String) -> u64 {
+        closeness_bytes(self.as_bytes(), other.as_bytes())
+    }
+}
+
+impl PartialEq<Vec<u8>> for Vec<u8> {
+    fn ceq(&self, other: &Vec<u8>, self_inner: &mut String, other_inner: &mut String) -> bool {
+        ceq_bytes(self, other, self_inner, other_inner)
+    }
+
+    fn ceq_as(
+        &self,
+        other: &Vec<u8>,
+        comparison: &Comparison,
+        self_inner: &mut String,
+        other_inner: &mut String,
+    ) -> bool {
+        let mismatch = crate::checker::compare::mismatch_as(&self[..], &other[..], comparison);
+        report(mismatch, self_inner, other_inner)
+    }
+
+    fn closeness(&self, other: &Vec<u8>) -> u64 {
+        closeness_bytes(self, other)
+    }
+}
+
+impl PartialEq<bytes::Bytes> for bytes::Bytes {
+    fn ceq(&self, other: &bytes::Bytes, self_inner: &mut String, other_inner: &mut String) -> bool {
+        ceq_bytes(self, other, self_inner, other_inner)
+    }
+
+    fn ceq_as(
+        &self,
+        other: &bytes::Bytes,
+        comparison: &Comparison,
+        self_inner: &mut String,
+        other_inner: &mut String,
+    ) -> bool {
+        let mismatch = crate::checker::compare::mismatch_as(&self[..], &other[..], comparison);
+        report(mismatch, self_inner, other_inner)
+    }
+
+    fn closeness(&self, other: &bytes::Bytes) -> u64 {
+        closeness_bytes(self, other)
+    }
+}
+
+/// Byte-exact comparison of in-memory values. On failure, the string
+/// representations are excerpts of the first line that differs, with invalid
+/// UTF-8 replaced.
+fn ceq_bytes(this: &[u8], other: &[u8], self_inner: &mut String, other_inner: &mut String) -> bool {
+    let mismatch = crate::checker::compare::first_mismatch(this, other);
+    report(mismatch, self_inner, other_inner)
+}
+
+/// Puts the excerpts of the mismatch, if any, in the string representations.
+fn report(
+    mismatch: std::io::Result<Option<crate::checker::compare::Mismatch>>,
+    self_inner: &mut String,
+    other_inner: &mut String,
+) -> bool {
+    match mismatch {
+        Ok(None) => true,
+        Ok(Some(m)) => {
+            self_inner.push_str(&m.this);
+            other_inner.push_str(&m.other);
+            false
+        }
+        Err(e) => {
+            self_inner.push_str(&e.to_string());
+            false
+        }
+    }
+}
+
+fn closeness_bytes(this: &[u8], other: &[u8]) -> u64 {
+    match crate::checker::compare::first_mismatch(this, other) {
+        Ok(Some(m)) => m.offset,
+        _ => 0,
     }
 }
@@ -0,0 +1,202 @@
+//! Discovery of tests from an input directory and a reference directory.
+
+/// Pairs the files of an input directory with the files of a reference
+/// directory by the name of the test.
+///
+/// Patterns are paths relative to their directory, where `*` stands for the
+/// name of the test: `*.in` and `*.ok` pair `001.in` with `001.ok`, while
+/// `*/input.txt` and `*/output.txt` pair `test1/input.txt` with
+/// `test1/output.txt`. Files that don't match the pattern are ignored.
+///
+/// If all the test names are numbers, they become the keys of the tests.
+/// Otherwise, tests are numbered from 1 in the natural order of their names.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Discovery {
+    pub ins: std::path::PathBuf,
+    pub refs: std::path::PathBuf,
+    #[cfg_attr(feature = "use-serde", serde(default = "default_in_pattern"))]
+    pub in_pattern: String,
+    #[cfg_attr(feature = "use-serde", serde(default = "default_ref_pattern"))]
+    pub ref_pattern: String,
+}
+
+fn default_in_pattern() -> String {
+    String::from("*.in")
+}
+
+fn default_ref_pattern() -> String {
+    String::from("*.ref")
+}
+
+impl Discovery {
+    /// Discovery of `*.in` and `*.ref` files.
+    pub fn new<P, Q>(ins: P, refs: Q) -> Self
+    where
+        P: AsRef<std::path::Path>,
+        Q: AsRef<std::path::Path>,
+    {
+        Self {
+            ins: std::path::PathBuf::from(ins.as_ref()),
+            refs: std::path::PathBuf::from(refs.as_ref()),
+            in_pattern: default_in_pattern(),
+            ref_pattern: default_ref_pattern(),
+        }
+    }
+
+    /// Changes the patterns of the input and reference files.
+    pub fn with_patterns<S, R>(mut self, in_pattern: S, ref_pattern: R) -> Self
+    where
+        S: AsRef<str>,
+        R: AsRef<str>,
+    {
+        self.in_pattern = String::from(in_pattern.as_ref());
+        self.ref_pattern = String::from(ref_pattern.as_ref());
+        self
+    }
+
+    /// Scans both directories and pairs the files, ready to be used as
+    /// [`in_refs`](crate::checker::CheckerConfig::in_refs). Inputs without a
+    /// reference and references without an input are reported as an error.
+    pub fn discover(
+        &self,
+    ) -> Result<
+        std::collections::BTreeMap<usize, (std::path::PathBuf, std::path::PathBuf)>,
+        anyhow::Error,
+    > {
+        let mut ins = scan(&self.ins, &self.in_pattern)?;
+        let mut refs = scan(&self.refs, &self.ref_pattern)?;
+
+        // Report every unmatched file at once.
+        let mut unmatched = Vec::new();
+        for (name, path) in &ins {
+            if !refs.contains_key(name) {
+                unmatched.push(format!("{:?} has no reference", path));
+            }
+        }
+        for (name, path) in &refs {
+            if !ins.contains_key(name) {
+                unmatched.push(format!("{:?} has no input", path));
+            }
+        }
+
+        if !unmatched.is_empty() {
+            return Err(anyhow::format_err!(
+                "Unmatched test files:\n{}",
+                unmatched.join("\n")
+            ));
+        }
+
+        let mut names = ins.keys().cloned().collect::<Vec<_>>();
+
+        let numbered = names
+            .iter()
+            .map(|n| n.parse::<usize>().ok())
+            .collect::<Option<Vec<_>>>()
+            // Names like `1` and `01` would collide.
+            .filter(|n| n.iter().collect::<std::collections::BTreeSet<_>>().len() == n.len());
+
+        let keys = match numbered {
+            Some(numbers) => numbers,
+            None => {
+                names.sort_by_key(|n| natural_key(n));
+                (1..=names.len()).collect()
+            }
+        };
+
+        Ok(keys
+            .into_iter()
+            .zip(names)
+            .map(|(key, name)| {
+                (
+                    key,
+                    (ins.remove(&name).unwrap(), refs.remove(&name).unwrap()),
+                )
+            })
+            .collect())
+    }
+}
+
+/// Finds the files in `dir` that match the pattern, by the name of the test.
+fn scan(
+    dir: &std::path::Path,
+    pattern: &str,
+) -> Result<std::collections::BTreeMap<String, std::path::PathBuf>, anyhow::Error> {
+    let regex = match pattern.split_once('*') {
+        Some((prefix, suffix)) if !suffix.contains('*') => regex::Regex::new(&format!(
+            "^{}(.+){}$",
+            regex::escape(prefix),
+            regex::escape(suffix)
+        ))?,
+        _ => {
+            return Err(anyhow::format_err!(
+                "Pattern {:?} should contain exactly one `*`",
+                pattern
+            ));
+        }
+    };
+
+    let mut files = std::collections::BTreeMap::new();
+    let mut dirs = vec![std::path::PathBuf::from(dir)];
+
+    while let Some(current) = dirs.pop() {
+        let entries = match std::fs::read_dir(&current) {
+            Ok(e) => e,
+            Err(e) => {
+                return Err(anyhow::format_err!("{:?}: {}", current, e));
+            }
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+
+            // Patterns always use `/`, whatever the platform.
+            let relative = path
+                .strip_prefix(dir)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if let Some(name) = regex.captures(&relative).and_then(|c| c.get(1)) {
+                files.insert(String::from(name.as_str()), path);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Sorting key that orders `test2` before `test10`.
+fn natural_key(name: &str) -> Vec<(String, usize)> {
+    let mut key = Vec::new();
+    let mut text = String::new();
+    let mut number: Option<usize> = None;
+
+    for c in name.chars() {
+        match c.to_digit(10) {
+            Some(d) => {
+                number = Some(
+                    number
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(d as usize),
+                );
+            }
+            None => {
+                if let Some(n) = number.take() {
+                    key.push((std::mem::take(&mut text), n));
+                }
+                text.push(c);
+            }
+        }
+    }
+    key.push((text, number.unwrap_or(0)));
+
+    key
+}
@@ -1,9 +1,26 @@
 //! Traits, structs and helpers related to the checker.
 #![allow(dead_code)]
 
+pub(crate) mod compare;
 pub(crate) mod config;
+pub(crate) mod discovery;
+pub(crate) mod runner;
+pub(crate) mod structured;
+pub(crate) mod tree;
+pub(crate) mod validation;
+
+#[cfg(test)]
+pub mod tests;
+
+pub use config::{
+    CheckerConfig, Comparison, Decision, ExecutionPolicy, Invocation, IoMode, MonitorType,
+    OutputType, PartialEq,
+};
+pub use discovery::Discovery;
+#[cfg(unix)]
+pub use runner::{LocalRunner, Stdin, Stdout, TestInput, TestOutput, OUTPUT_LIMIT, STDERR_LIMIT};
+pub use validation::{check_with, Constraints, IntegerRange, LineConstraint};
 
-pub use config::{CheckerConfig, MonitorType, OutputType, PartialEq};
 /// Errors that could occur running a test.
 #[derive(thiserror::Error, Debug)]
 #[non_exhaustive]
@@ -14,6 +31,12 @@ pub enum Error {
     #[error("Running test failed: {0}")]
     TestError(String),
 
+    #[error("Running test failed: Time exceeded!")]
+    TimeExceeded,
+
+    #[error("Running test failed: Output limit exceeded!")]
+    OutputLimitExceeded,
+
     #[error("Comparing output and reference failed with: {0}")]
     CompareError(String),
 }
@@ -34,8 +57,113 @@ pub enum Output {
         #[cfg_attr(feature = "use-serde", serde(skip_serializing_if = "Option::is_none"))]
         message: Option<String>,
     },
+    /// The test did not run because of the
+    /// [`ExecutionPolicy`](crate::checker::ExecutionPolicy).
+    #[cfg_attr(feature = "use-serde", serde(rename = "skipped"))]
+    Skipped,
+    /// The test case was skipped by its own test suite, with the reason if
+    /// any.
+    #[cfg_attr(feature = "use-serde", serde(rename = "ignored"))]
+    Ignored {
+        #[cfg_attr(feature = "use-serde", serde(skip_serializing_if = "Option::is_none"))]
+        message: Option<String>,
+    },
+    /// The repeated runs of the test agree on the verdict.
+    #[cfg_attr(feature = "use-serde", serde(rename = "repeated"))]
+    Repeated {
+        verdict: Box<Output>,
+        runs: Vec<Run>,
+    },
+    /// The repeated runs of the test disagree. The verdict is given by the
+    /// [`Decision`](crate::checker::Decision).
+    #[cfg_attr(feature = "use-serde", serde(rename = "unstable"))]
+    Unstable {
+        verdict: Box<Output>,
+        runs: Vec<Run>,
+    },
+}
+
+impl Output {
+    /// Whether the test passed. Repeated and unstable tests passed if their
+    /// verdict did.
+    pub fn passed(&self) -> bool {
+        match self {
+            Output::Passed | Output::Score { message: None, .. } => true,
+            Output::Repeated { verdict, .. } | Output::Unstable { verdict, .. } => verdict.passed(),
+            _ => false,
+        }
+    }
+}
+
+/// One of the repeated runs of a test.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Run {
+    pub verdict: Verdict,
+    pub output: Output,
+    pub time: std::time::Duration,
+}
+
+/// Kind of verdict of a run, telling apart the ways it can fail. Runs that
+/// fail in different ways disagree.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, std::cmp::PartialEq, Eq)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Verdict {
+    #[cfg_attr(feature = "use-serde", serde(rename = "passed"))]
+    Passed,
+    /// The output doesn't match any reference.
+    #[cfg_attr(feature = "use-serde", serde(rename = "wrong_answer"))]
+    WrongAnswer,
+    #[cfg_attr(feature = "use-serde", serde(rename = "time_exceeded"))]
+    TimeExceeded,
+    #[cfg_attr(feature = "use-serde", serde(rename = "output_limit_exceeded"))]
+    OutputLimitExceeded,
+    /// Any other error, such as a crash or a non-zero exit status.
+    #[cfg_attr(feature = "use-serde", serde(rename = "runtime_error"))]
+    RuntimeError,
+}
+
+impl Verdict {
+    /// Kind of verdict of a run with the given output, if it fails.
+    fn of_failure<O>(output: &Result<O, crate::checker::Error>) -> Self {
+        match output {
+            Ok(_) => Verdict::WrongAnswer,
+            Err(crate::checker::Error::TimeExceeded) => Verdict::TimeExceeded,
+            Err(crate::checker::Error::OutputLimitExceeded) => Verdict::OutputLimitExceeded,
+            Err(_) => Verdict::RuntimeError,
+        }
+    }
+}
+
+/// Progress events of a checker, given to the handler set with
+/// [`Checker::with_events`](crate::checker::Checker::with_events).
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    /// The solution started compiling (or being prepared to run).
+    #[cfg_attr(feature = "use-serde", serde(rename = "compile_started"))]
+    CompileStarted,
+    /// The solution finished compiling, with the error if it failed.
+    #[cfg_attr(feature = "use-serde", serde(rename = "compile_finished"))]
+    CompileFinished {
+        #[cfg_attr(feature = "use-serde", serde(skip_serializing_if = "Option::is_none"))]
+        error: Option<String>,
+    },
+    #[cfg_attr(feature = "use-serde", serde(rename = "test_started"))]
+    TestStarted { test: usize },
+    #[cfg_attr(feature = "use-serde", serde(rename = "test_finished"))]
+    TestFinished { test: usize, output: Output },
+    /// All tests are done.
+    #[cfg_attr(feature = "use-serde", serde(rename = "done"))]
+    Done,
 }
 
+/// Handler for the progress [`Event`](crate::checker::Event)s. It may be called
+/// from multiple threads at once.
+pub type EventHandler = std::sync::Arc<dyn Fn(Event) + Send + Sync>;
+
 /// Checker is defined by a [CheckerConfig](crate::checker::CheckerConfig) and a
 /// runner (a closure a closure that defines the way a command should run.
 pub struct Checker<I, O, F, T, S, P>
@@ -52,6 +180,7 @@ where
 {
     config: crate::checker::config::CheckerConfig<I, O, P>,
     runner: F,
+    on_event: Option<EventHandler>,
     _phantom_t: std::marker::PhantomData<T>,
 }
 
@@ -74,101 +203,371 @@ where
         Self {
             config,
             runner,
+            on_event: None,
             _phantom_t: std::marker::PhantomData::default(),
         }
     }
 
-    /// Runs the checker for a command given.
+    /// Sets a handler that gets the progress of the tests as they run.
+    pub fn with_events<E>(mut self, on_event: E) -> Self
+    where
+        E: Fn(Event) + Send + Sync + 'static,
+    {
+        self.on_event = Some(std::sync::Arc::new(on_event));
+        self
+    }
+
+    /// Runs the checker for a command given, one test after the other on the
+    /// current thread. Tests left out by the
+    /// [`policy`](crate::checker::CheckerConfig::policy) are marked as
+    /// [`Skipped`](crate::checker::Output::Skipped).
     pub fn run(self, command: &T) -> std::collections::BTreeMap<usize, crate::checker::Output> {
-        let keys = self
-            .config
+        let stopped = std::sync::Mutex::new(std::collections::BTreeSet::new());
+
+        let results = self
+            .inputs()
+            .into_iter()
+            .map(|(key, input)| (key, self.run_test(command, key, input, &stopped)))
+            .collect();
+
+        self.emit(Event::Done);
+
+        results
+    }
+
+    /// Runs the checker for a command given and consumes the checker. Same as
+    /// [`run`](crate::checker::Checker::run), kept for the callers of the
+    /// earlier versions.
+    pub fn run_once(
+        self,
+        command: &T,
+    ) -> std::collections::BTreeMap<usize, crate::checker::Output> {
+        self.run(command)
+    }
+
+    /// Runs the checker for a command given, like
+    /// [`run`](crate::checker::Checker::run), but with the tests scheduled on
+    /// a pool of [`workers`](crate::checker::CheckerConfig::workers) threads,
+    /// each giving the runner one test at a time.
+    pub fn run_parallel(
+        self,
+        command: &T,
+    ) -> std::collections::BTreeMap<usize, crate::checker::Output>
+    where
+        F: Sync,
+        T: Sync,
+        I: Sync,
+        O: Sync,
+        P: Sync,
+    {
+        let inputs = self.inputs();
+        let workers = self.config.workers.clamp(1, inputs.len().max(1));
+        let pin_cpus = self.config.pin_cpus;
+
+        let queue = std::sync::Mutex::new(inputs.into_iter());
+        let results = std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+        // Groups that had a failure. Their remaining tests are skipped.
+        let stopped = std::sync::Mutex::new(std::collections::BTreeSet::new());
+
+        std::thread::scope(|scope| {
+            for worker in 0..workers {
+                let (checker, queue, results, stopped) = (&self, &queue, &results, &stopped);
+
+                scope.spawn(move || {
+                    if pin_cpus {
+                        pin_worker(worker);
+                    }
+
+                    loop {
+                        let next = queue.lock().unwrap().next();
+                        let Some((key, input)) = next else {
+                            break;
+                        };
+
+                        let output = checker.run_test(command, key, input, stopped);
+                        results.lock().unwrap().insert(key, output);
+                    }
+                });
+            }
+        });
+
+        self.emit(Event::Done);
+
+        results.into_inner().unwrap()
+    }
+
+    /// Inputs of the tests, by key.
+    fn inputs(&self) -> std::collections::BTreeMap<usize, &I> {
+        self.config
             .in_refs
             .iter()
             .map(|m| (*(m.0), &m.1.0))
-            .collect::<std::collections::BTreeMap<_, _>>();
+            .collect()
+    }
 
-        let outputs = (self.runner)(&command, keys);
+    fn emit(&self, event: Event) {
+        if let Some(on_event) = &self.on_event {
+            (on_event)(event);
+        }
+    }
 
-        let mut scored: bool = false;
-        let mut score_per_test: usize = 0;
-        if let crate::checker::config::OutputType::Scored { per_test } = self.config.output_type {
-            scored = true;
-            score_per_test = per_test;
+    /// Runs a test as many times as it repeats and judges it, unless a test of
+    /// its group failed in `stopped`, where it adds its group if it fails.
+    fn run_test(
+        &self,
+        command: &T,
+        key: usize,
+        input: &I,
+        stopped: &std::sync::Mutex<std::collections::BTreeSet<usize>>,
+    ) -> crate::checker::Output {
+        let policy = &self.config.policy;
+
+        if let Some(group) = policy.group_of(key) {
+            if stopped.lock().unwrap().contains(&group) {
+                self.emit(Event::TestFinished {
+                    test: key,
+                    output: crate::checker::Output::Skipped,
+                });
+                return crate::checker::Output::Skipped;
+            }
         }
 
-        let results: std::collections::BTreeMap<_, _> = outputs
-            .into_iter()
-            .map(|m| match m.1 {
-                Ok(output) => {
-                    let mut output_inner = String::new();
-                    let mut ref_inner = String::new();
+        self.emit(Event::TestStarted { test: key });
+
+        let references = std::iter::once(&self.config.in_refs.get(&key).unwrap().1)
+            .chain(self.config.alternatives.get(&key).into_iter().flatten())
+            .collect::<Vec<_>>();
+        let comparison = self
+            .config
+            .per_test_comparison
+            .get(&key)
+            .unwrap_or(&self.config.comparison);
+
+        // Every run of the test, with its time.
+        let mut runs = Vec::new();
+
+        for _ in 0..self.config.repeats.max(1) {
+            let start = std::time::Instant::now();
+            let outputs = (self.runner)(command, std::collections::BTreeMap::from([(key, input)]));
+            let time = start.elapsed();
+
+            for (_, output) in outputs {
+                // Judging takes the output, so the error is kept first.
+                let failure = Verdict::of_failure(&output);
+                let (passed, output) = judge(
+                    output,
+                    &references,
+                    comparison,
+                    &self.config.output_type,
+                    &self.config.io,
+                );
+                let verdict = if passed { Verdict::Passed } else { failure };
+                runs.push((
+                    passed,
+                    Run {
+                        verdict,
+                        output,
+                        time,
+                    },
+                ));
+            }
+        }
+
+        let (passed, verdict) = decide(runs, self.config.decision);
+
+        if !passed {
+            if let Some(group) = policy.group_of(key) {
+                stopped.lock().unwrap().insert(group);
+            }
+        }
+
+        self.emit(Event::TestFinished {
+            test: key,
+            output: verdict.clone(),
+        });
+
+        verdict
+    }
+}
+
+/// Gives the verdict of a test from its runs, along with all of them. It is
+/// flagged as [`Unstable`](crate::checker::Output::Unstable) if they disagree
+/// on the kind of verdict.
+fn decide(
+    mut runs: Vec<(bool, Run)>,
+    decision: crate::checker::Decision,
+) -> (bool, crate::checker::Output) {
+    if runs.len() == 1 {
+        let (passed, run) = runs.remove(0);
+        return (passed, run.output);
+    }
+
+    let passes = runs.iter().filter(|r| r.0).count();
+
+    let passed = match decision {
+        crate::checker::Decision::Worst => passes == runs.len(),
+        crate::checker::Decision::Majority => 2 * passes > runs.len(),
+        _ => passes == runs.len(),
+    };
+
+    // The first run that agrees with the verdict.
+    let verdict = match runs.iter().find(|r| r.0 == passed) {
+        Some(r) => r.1.output.clone(),
+        None => crate::checker::Output::Failed(String::from("No run of the test.")),
+    };
+
+    let stable = runs.iter().all(|r| r.1.verdict == runs[0].1.verdict);
+    let (verdict, runs) = (Box::new(verdict), runs.into_iter().map(|r| r.1).collect());
+
+    if stable {
+        (passed, crate::checker::Output::Repeated { verdict, runs })
+    } else {
+        (passed, crate::checker::Output::Unstable { verdict, runs })
+    }
+}
+
+/// Compares the output of a test with its references, passing if it matches
+/// any of them. Returns whether the test passed, along with its verdict.
+fn judge<O>(
+    output: Result<O, crate::checker::Error>,
+    references: &[&O],
+    comparison: &crate::checker::Comparison,
+    output_type: &crate::checker::config::OutputType,
+    io: &crate::checker::IoMode,
+) -> (bool, crate::checker::Output)
+where
+    O: crate::checker::PartialEq<O>,
+{
+    let mut scored: bool = false;
+    let mut score_per_test: usize = 0;
+    if let crate::checker::config::OutputType::Scored { per_test } = output_type {
+        scored = true;
+        score_per_test = *per_test;
+    }
+
+    match output {
+        Ok(output) => {
+            // Closest reference so far, by index.
+            let mut closest: Option<(usize, u64, String, String)> = None;
 
-                    if output.ceq(
-                        &self.config.in_refs.get(&m.0).unwrap().1,
+            for (i, reference) in references.iter().enumerate() {
+                let mut output_inner = String::new();
+                let mut ref_inner = String::new();
+
+                let equal = match io {
+                    crate::checker::IoMode::Files { outputs, .. } => output.ceq_files(
+                        reference,
+                        outputs,
+                        comparison,
                         &mut output_inner,
                         &mut ref_inner,
-                    ) {
-                        return if scored {
-                            (
-                                m.0,
-                                crate::checker::Output::Score {
-                                    score: score_per_test,
-                                    message: None,
-                                },
-                            )
-                        } else {
-                            (m.0, crate::checker::Output::Passed)
-                        };
-                    } else {
-                        return if scored {
-                            (
-                                m.0,
-                                crate::checker::Output::Score {
-                                    score: 0,
-                                    message: Some(format!(
-                                        "Expected: {}\nBut got: {}\n",
-                                        ref_inner, output_inner
-                                    )),
-                                },
-                            )
-                        } else {
-                            (
-                                m.0,
-                                crate::checker::Output::Failed(format!(
-                                    "Expected: {}\nBut got: {}\n",
-                                    ref_inner, output_inner
-                                )),
-                            )
-                        };
-                    }
-                }
-                Err(e) => {
-                    return if scored {
-                        (
-                            m.0,
+                    ),
+                    _ => output.ceq_as(reference, comparison, &mut output_inner, &mut ref_inner),
+                };
+
+                if equal {
+                    if scored {
+                        return (
+                            true,
                             crate::checker::Output::Score {
-                                score: 0,
-                                message: Some(format!("{}", e.to_string())),
+                                score: score_per_test,
+                                message: None,
                             },
-                        )
+                        );
                     } else {
-                        (
-                            m.0,
-                            crate::checker::Output::Failed(format!("{}", e.to_string())),
-                        )
-                    };
+                        return (true, crate::checker::Output::Passed);
+                    }
                 }
-            })
-            .collect();
 
-        results
+                let closeness = if references.len() > 1 {
+                    output.closeness(reference)
+                } else {
+                    0
+                };
+
+                let closer = match &closest {
+                    Some(c) => closeness > c.1,
+                    None => true,
+                };
+
+                if closer {
+                    closest = Some((i, closeness, output_inner, ref_inner));
+                }
+            }
+
+            let message = match closest {
+                Some((i, _, output_inner, ref_inner)) => {
+                    let mut message =
+                        format!("Expected: {}\nBut got: {}\n", ref_inner, output_inner);
+                    if references.len() > 1 {
+                        let name = references[i]
+                            .name()
+                            .unwrap_or_else(|| format!("{} of {}", i + 1, references.len()));
+                        message.push_str(&format!("Closest reference: {}\n", name));
+                    }
+                    message
+                }
+                None => String::from("No reference to compare with.\n"),
+            };
+
+            if scored {
+                (
+                    false,
+                    crate::checker::Output::Score {
+                        score: 0,
+                        message: Some(message),
+                    },
+                )
+            } else {
+                (false, crate::checker::Output::Failed(message))
+            }
+        }
+        Err(e) => {
+            if scored {
+                (
+                    false,
+                    crate::checker::Output::Score {
+                        score: 0,
+                        message: Some(e.to_string()),
+                    },
+                )
+            } else {
+                (false, crate::checker::Output::Failed(e.to_string()))
+            }
+        }
     }
+}
 
-    /// Runs the checker for a command given and consumes the checker.
-    pub fn run_once(
-        self,
-        command: &T,
-    ) -> std::collections::BTreeMap<usize, crate::checker::Output> {
-        self.run(command)
+/// Pins the current thread to one of the CPUs it is allowed to run on. The
+/// tests it spawns inherit the affinity.
+#[cfg(target_os = "linux")]
+fn pin_worker(worker: usize) {
+    use nix::sched::{sched_getaffinity, sched_setaffinity, CpuSet};
+
+    // Pid 0 is the calling thread.
+    let this = nix::unistd::Pid::from_raw(0);
+
+    let allowed = match sched_getaffinity(this) {
+        Ok(a) => a,
+        Err(_) => {
+            return;
+        }
+    };
+
+    let cpus = (0..CpuSet::count())
+        .filter(|cpu| allowed.is_set(*cpu).unwrap_or(false))
+        .collect::<Vec<_>>();
+
+    if cpus.is_empty() {
+        return;
+    }
+
+    let mut set = CpuSet::new();
+    if set.set(cpus[worker % cpus.len()]).is_ok() {
+        let _ = sched_setaffinity(this, &set);
     }
 }
+
+#[cfg(not(target_os = "linux"))]
+fn pin_worker(_worker: usize) {}
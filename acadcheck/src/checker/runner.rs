@@ -0,0 +1,749 @@
+//! Runner that spawns the command locally for each test.
+#![cfg(unix)]
+#![cfg_attr(docsrs, doc(cfg(unix)))]
+
+use std::os::unix::process::CommandExt;
+
+use wait_timeout::ChildExt;
+
+/// Bytes of stderr kept for each test. The rest is read and discarded.
+pub const STDERR_LIMIT: usize = 64 * 1024;
+
+/// Bytes an in-memory output may have by default. Tests that write more fail.
+pub const OUTPUT_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Time the pipes of a test are still read after its process group is gone.
+/// Only processes that left the group can keep them open that long.
+const PIPE_GRACE: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Runs the command once per test as a local process, with the input file as
+/// stdin and `$out_dir/$key.out` as stdout.
+///
+/// Stderr is captured, up to [`STDERR_LIMIT`](crate::checker::STDERR_LIMIT)
+/// bytes, and written in `$out_dir/$key.err` next to the output. Tests with
+/// in-memory outputs don't keep it.
+///
+/// Inputs and outputs may also be kept in memory (`String`, `Vec<u8>` or
+/// `bytes::Bytes`), in which case they go through pipes and nothing is written
+/// on disk. They are limited to [`OUTPUT_LIMIT`](crate::checker::OUTPUT_LIMIT)
+/// bytes, unless set otherwise. See [`TestInput`](crate::checker::TestInput)
+/// and [`TestOutput`](crate::checker::TestOutput).
+///
+/// With file-based [`IoMode::Files`](crate::checker::IoMode::Files), each test
+/// runs in a fresh `$out_dir/$key/` directory instead, and the test output is
+/// that directory. Relative paths in the command are resolved from there. The
+/// [`fixture`](crate::checker::Invocation::fixture) of a test is copied there
+/// first.
+///
+/// Tests with an [`Invocation`](crate::checker::Invocation) get its arguments
+/// and environment variables, and no stdin if it is turned off.
+///
+/// Each test leads its own process group, so on timeout (and after it exits)
+/// everything it forked is killed and reaped.
+///
+/// ```no_run
+/// use acadcheck::checker::LocalRunner;
+///
+/// let runner = LocalRunner::new("/tmp/out")
+///     .with_user("sandbox", "restricted")
+///     .unwrap()
+///     .with_env("LC_ALL", "C");
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocalRunner {
+    out_dir: std::path::PathBuf,
+    timeout: Option<std::time::Duration>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    env: Vec<(std::ffi::OsString, std::ffi::OsString)>,
+    env_clear: bool,
+    invocations: std::collections::BTreeMap<usize, crate::checker::Invocation>,
+    io: crate::checker::IoMode,
+    output_limit: usize,
+    /// Directory the commands run in with standard I/O, if not the current
+    /// one.
+    dir: Option<std::path::PathBuf>,
+}
+
+impl LocalRunner {
+    /// Returns a runner that writes the outputs in `out_dir`, without any
+    /// monitors and running as the current user.
+    pub fn new<P>(out_dir: P) -> Self
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Self {
+            out_dir: std::path::PathBuf::from(out_dir.as_ref()),
+            timeout: None,
+            uid: None,
+            gid: None,
+            env: Vec::new(),
+            env_clear: false,
+            invocations: std::collections::BTreeMap::new(),
+            io: crate::checker::IoMode::Standard,
+            output_limit: OUTPUT_LIMIT,
+            dir: None,
+        }
+    }
+
+    /// Adds the monitors the runner should enforce.
+    pub fn with_monitors<'a, P>(mut self, monitors: P) -> Self
+    where
+        P: IntoIterator<Item = &'a crate::checker::MonitorType>,
+    {
+        for monitor in monitors {
+            if let crate::checker::MonitorType::Timeout { limit } = monitor {
+                self.timeout = Some(*limit);
+            }
+        }
+        self
+    }
+
+    /// Runs the tests as the given user and group ids.
+    pub fn with_identity(mut self, uid: u32, gid: u32) -> Self {
+        self.uid = Some(uid);
+        self.gid = Some(gid);
+        self
+    }
+
+    /// Runs the tests as the given user and group. If any of them can't be
+    /// found, it will return an error.
+    pub fn with_user<U, G>(self, user: U, group: G) -> Result<Self, anyhow::Error>
+    where
+        U: AsRef<str>,
+        G: AsRef<str>,
+    {
+        let uid = match nix::unistd::User::from_name(user.as_ref()) {
+            Ok(Some(u)) => u.uid.as_raw(),
+            _ => {
+                return Err(anyhow::format_err!("User {} not found", user.as_ref()));
+            }
+        };
+
+        let gid = match nix::unistd::Group::from_name(group.as_ref()) {
+            Ok(Some(g)) => g.gid.as_raw(),
+            _ => {
+                return Err(anyhow::format_err!("Group {} not found", group.as_ref()));
+            }
+        };
+
+        Ok(self.with_identity(uid, gid))
+    }
+
+    /// Sets an environment variable for the tests.
+    pub fn with_env<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: AsRef<std::ffi::OsStr>,
+        V: AsRef<std::ffi::OsStr>,
+    {
+        self.env.push((
+            std::ffi::OsString::from(key.as_ref()),
+            std::ffi::OsString::from(value.as_ref()),
+        ));
+        self
+    }
+
+    /// Tests will not inherit the environment of the checker, only the
+    /// variables set with [`with_env`](Self::with_env).
+    pub fn with_env_clear(mut self) -> Self {
+        self.env_clear = true;
+        self
+    }
+
+    /// Sets the most bytes an in-memory output may have.
+    pub fn with_output_limit(mut self, bytes: usize) -> Self {
+        self.output_limit = bytes;
+        self
+    }
+
+    /// Sets how specific tests are invoked, usually the
+    /// [`invocations`](crate::checker::CheckerConfig::invocations) of the
+    /// checker config.
+    pub fn with_invocations<'a, P>(mut self, invocations: P) -> Self
+    where
+        P: IntoIterator<Item = (&'a usize, &'a crate::checker::Invocation)>,
+    {
+        self.invocations.extend(
+            invocations
+                .into_iter()
+                .map(|(key, invocation)| (*key, invocation.clone())),
+        );
+        self
+    }
+
+    /// Sets where the tests read their input and write their outputs, usually
+    /// the [`io`](crate::checker::CheckerConfig::io) of the checker config.
+    pub fn with_io(mut self, io: &crate::checker::IoMode) -> Self {
+        self.io = io.clone();
+        self
+    }
+
+    /// Output file of a test: `$out_dir/$key.out`.
+    pub fn out_file(&self, key: usize) -> std::path::PathBuf {
+        self.out_dir.join(format!("{}.out", key))
+    }
+
+    /// Stderr file of a test: `$out_dir/$key.err`.
+    pub fn err_file(&self, key: usize) -> std::path::PathBuf {
+        self.out_dir.join(format!("{}.err", key))
+    }
+
+    /// Working directory of a test with file-based I/O: `$out_dir/$key/`.
+    pub fn work_dir(&self, key: usize) -> std::path::PathBuf {
+        self.out_dir.join(key.to_string())
+    }
+
+    /// Empties the working directory of a test, seeds it with the fixture
+    /// tree, if any, and copies its input there, everything owned by the user
+    /// the test runs as.
+    fn prepare<I>(
+        &self,
+        work_dir: &std::path::Path,
+        name: &str,
+        fixture: Option<&std::path::Path>,
+        input: &I,
+    ) -> Result<(), crate::checker::Error>
+    where
+        I: TestInput,
+    {
+        let error = |e: std::io::Error| crate::checker::Error::TestError(e.to_string());
+
+        if work_dir.exists() {
+            std::fs::remove_dir_all(work_dir).map_err(error)?;
+        }
+        std::fs::create_dir_all(work_dir).map_err(error)?;
+
+        let mut created = vec![std::path::PathBuf::from(work_dir)];
+
+        if let Some(fixture) = fixture {
+            copy_tree(fixture, work_dir, &mut created).map_err(error)?;
+        }
+
+        let copy = work_dir.join(name);
+        match input.stdin()? {
+            Stdin::File(mut file) => {
+                let mut to = std::fs::File::create(&copy).map_err(error)?;
+                std::io::copy(&mut file, &mut to).map_err(error)?;
+            }
+            Stdin::Bytes(bytes) => std::fs::write(&copy, bytes).map_err(error)?,
+        }
+        created.push(copy);
+
+        if self.uid.is_some() || self.gid.is_some() {
+            let (uid, gid) = (
+                self.uid.map(nix::unistd::Uid::from_raw),
+                self.gid.map(nix::unistd::Gid::from_raw),
+            );
+            for path in created {
+                nix::unistd::chown(&path, uid, gid)
+                    .map_err(|e| crate::checker::Error::TestError(e.to_string()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs the command for all inputs. This is the runner expected by
+    /// [`Checker::new`](crate::checker::Checker::new).
+    pub fn run<T, S, I, O>(
+        &self,
+        command: &T,
+        inputs: std::collections::BTreeMap<usize, &I>,
+    ) -> std::collections::BTreeMap<usize, Result<O, crate::checker::Error>>
+    where
+        T: AsRef<[S]>,
+        S: AsRef<std::ffi::OsStr>,
+        I: TestInput,
+        O: TestOutput,
+    {
+        // Create output directory in case it doesn't exist.
+        if !O::IN_MEMORY && !self.out_dir.exists() {
+            let _ = std::fs::create_dir_all(&self.out_dir);
+        }
+
+        // Run the tests one by one, so they don't compete for the CPU. The
+        // checker decides how many runs happen in parallel.
+        inputs
+            .into_iter()
+            .map(|(key, input)| (key, self.run_test(command.as_ref(), key, input)))
+            .collect()
+    }
+
+    /// Borrows the runner as a closure that can be given to
+    /// [`Checker::new`](crate::checker::Checker::new).
+    pub fn as_fn<T, S, I, O>(
+        &self,
+    ) -> impl Fn(
+        &T,
+        std::collections::BTreeMap<usize, &I>,
+    ) -> std::collections::BTreeMap<usize, Result<O, crate::checker::Error>>
+    + Sync
+    + '_
+    where
+        T: AsRef<[S]>,
+        S: AsRef<std::ffi::OsStr>,
+        I: TestInput,
+        O: TestOutput,
+    {
+        move |command, inputs| self.run(command, inputs)
+    }
+
+    /// Runs a command once outside of the tests, such as a whole test suite,
+    /// as the same user, with `timeout` instead of the time limit of the
+    /// tests. It runs in `work_dir`, without stdin. A failing exit status is
+    /// an error.
+    ///
+    /// `out_dir` is created anew for the run, owned by the user the command
+    /// runs as, and its stdout and stderr are saved there as `0.out` and
+    /// `0.err`. Once the command is done, the checker takes the directory
+    /// back, so what the command left there can't be changed any more.
+    pub fn run_command<S>(
+        &self,
+        command: &[S],
+        work_dir: Option<&std::path::Path>,
+        out_dir: &std::path::Path,
+        timeout: std::time::Duration,
+    ) -> Result<(), crate::checker::Error>
+    where
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let error =
+            |e: std::io::Error| crate::checker::Error::TestError(format!("{:?}: {}", out_dir, e));
+
+        // Nothing may be there before the command runs.
+        if std::fs::symlink_metadata(out_dir).is_ok() {
+            std::fs::remove_dir_all(out_dir).map_err(error)?;
+        }
+        std::fs::create_dir_all(out_dir).map_err(error)?;
+
+        let owned = self.uid.is_some() || self.gid.is_some();
+        if owned {
+            let (uid, gid) = (
+                self.uid.map(nix::unistd::Uid::from_raw),
+                self.gid.map(nix::unistd::Gid::from_raw),
+            );
+            nix::unistd::chown(out_dir, uid, gid)
+                .map_err(|e| crate::checker::Error::TestError(e.to_string()))?;
+        }
+
+        let runner = Self {
+            out_dir: out_dir.to_path_buf(),
+            timeout: Some(timeout),
+            invocations: std::collections::BTreeMap::new(),
+            io: crate::checker::IoMode::Standard,
+            dir: work_dir.map(std::path::Path::to_path_buf),
+            ..self.clone()
+        };
+        let run = runner.run_test::<_, _, std::path::PathBuf>(command, 0, &Vec::<u8>::new());
+
+        if owned {
+            use std::os::unix::fs::PermissionsExt;
+
+            nix::unistd::chown(
+                out_dir,
+                Some(nix::unistd::geteuid()),
+                Some(nix::unistd::getegid()),
+            )
+            .map_err(|e| crate::checker::Error::TestError(e.to_string()))?;
+            std::fs::set_permissions(out_dir, std::fs::Permissions::from_mode(0o700))
+                .map_err(error)?;
+        }
+
+        run.map(|_| ())
+    }
+
+    /// Runs a test as the leader of a new process group. In-memory inputs are
+    /// written to stdin and in-memory outputs are read from stdout through
+    /// pipes, while the test runs.
+    fn run_test<S, I, O>(
+        &self,
+        command: &[S],
+        key: usize,
+        input: &I,
+    ) -> Result<O, crate::checker::Error>
+    where
+        S: AsRef<std::ffi::OsStr>,
+        I: TestInput,
+        O: TestOutput,
+    {
+        use std::io::Write;
+
+        // A stale stderr of an earlier run would be taken for the one of this
+        // test if it fails to start.
+        let _ = std::fs::remove_file(self.err_file(key));
+
+        if command.is_empty() {
+            return Err(crate::checker::Error::TestError(String::from(
+                "Command to run is empty.",
+            )));
+        }
+
+        let invocation = self.invocations.get(&key);
+
+        // Tests with file-based I/O run in their own directory.
+        let work_dir = match &self.io {
+            crate::checker::IoMode::Files { input: name, .. } => {
+                if O::IN_MEMORY {
+                    return Err(crate::checker::Error::TestError(String::from(
+                        "Tests with file-based I/O need file outputs.",
+                    )));
+                }
+
+                let work_dir = self.work_dir(key);
+                let fixture = invocation.and_then(|i| i.fixture.as_deref());
+                self.prepare(&work_dir, name, fixture, input)?;
+                Some(work_dir)
+            }
+            _ => None,
+        };
+
+        let mut cmd = std::process::Command::new(&command[0]);
+        cmd.args(&command[1..]).process_group(0);
+
+        if let Some(dir) = work_dir.as_ref().or(self.dir.as_ref()) {
+            cmd.current_dir(dir);
+        }
+
+        if self.env_clear {
+            cmd.env_clear();
+        }
+        cmd.envs(self.env.iter().map(|(k, v)| (k, v)));
+
+        if let Some(invocation) = invocation {
+            let path = input.path().map(|p| p.to_string_lossy());
+
+            for arg in &invocation.args {
+                match &path {
+                    Some(path) => cmd.arg(arg.replace("{input}", path)),
+                    None => cmd.arg(arg),
+                };
+            }
+            cmd.envs(&invocation.env);
+        }
+
+        if let Some(uid) = self.uid {
+            cmd.uid(uid);
+        }
+        if let Some(gid) = self.gid {
+            cmd.gid(gid);
+        }
+
+        let to_write = match invocation {
+            Some(crate::checker::Invocation { stdin: false, .. }) => {
+                cmd.stdin(std::process::Stdio::null());
+                None
+            }
+            _ => match input.stdin()? {
+                Stdin::File(file) => {
+                    cmd.stdin(std::process::Stdio::from(file));
+                    None
+                }
+                Stdin::Bytes(bytes) => {
+                    cmd.stdin(std::process::Stdio::piped());
+                    Some(bytes)
+                }
+            },
+        };
+
+        let out_file = self.out_file(key);
+
+        if O::IN_MEMORY {
+            cmd.stdout(std::process::Stdio::piped());
+        } else {
+            let output = std::fs::File::create(&out_file)
+                .map_err(|e| crate::checker::Error::TestError(e.to_string()))?;
+            cmd.stdout(std::process::Stdio::from(output));
+        }
+        cmd.stderr(std::process::Stdio::piped());
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| crate::checker::Error::TestError(e.to_string()))?;
+
+        let (stdin, stdout, stderr) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take());
+
+        // Pipes are handled on threads that aren't waited for, so a process
+        // that left the group and keeps them open can't block the test.
+        if let (Some(mut stdin), Some(bytes)) = (stdin, to_write) {
+            // The test may exit without reading all of it.
+            let bytes = bytes.to_vec();
+            std::thread::spawn(move || {
+                let _ = stdin.write_all(&bytes);
+            });
+        }
+
+        let reader = stdout.map(|stdout| spawn_reader(stdout, self.output_limit, false));
+        let errors = stderr.map(|stderr| spawn_reader(stderr, STDERR_LIMIT, true));
+
+        let status = wait_and_reap(child, self.timeout);
+
+        // Pipes are closed now that the whole group is gone.
+        let deadline = std::time::Instant::now() + PIPE_GRACE;
+        let captured = reader.map(|r| collect(&r, deadline));
+        let errors = errors.map(|r| collect(&r, deadline));
+
+        if let (false, Some(mut errors)) = (O::IN_MEMORY, errors) {
+            errors.truncate(STDERR_LIMIT);
+            let _ = std::fs::write(self.err_file(key), errors);
+        }
+
+        if matches!(&captured, Some(c) if c.len() > self.output_limit) {
+            return Err(crate::checker::Error::OutputLimitExceeded);
+        }
+
+        let status = status?;
+
+        if !status.success() {
+            return Err(crate::checker::Error::TestError(format!(
+                "Exit status: {}",
+                status
+            )));
+        }
+
+        match (captured, work_dir) {
+            (Some(bytes), _) => O::from_stdout(Stdout::Bytes(bytes)),
+            (None, Some(work_dir)) => O::from_stdout(Stdout::Dir(work_dir)),
+            (None, None) => O::from_stdout(Stdout::File(out_file)),
+        }
+    }
+}
+
+/// Copies the contents of a directory into another one, with their
+/// permissions, and adds every copied path to `created`.
+fn copy_tree(
+    from: &std::path::Path,
+    to: &std::path::Path,
+    created: &mut Vec<std::path::PathBuf>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            std::fs::create_dir(&target)?;
+            std::fs::set_permissions(&target, entry.metadata()?.permissions())?;
+            created.push(target.clone());
+            copy_tree(&entry.path(), &target, created)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+            created.push(target);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a pipe on its own thread and sends what it reads, up to `limit`
+/// bytes and one more to tell if there was more. After that, the rest is read
+/// and dropped if `drain` is set, and the pipe is closed otherwise.
+fn spawn_reader<R>(mut reader: R, limit: usize, drain: bool) -> std::sync::mpsc::Receiver<Vec<u8>>
+where
+    R: std::io::Read + Send + 'static,
+{
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        let mut kept = 0;
+        let mut chunk = [0u8; 8192];
+
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => {
+                    let n = n.min(limit.saturating_add(1) - kept);
+                    kept += n;
+
+                    if n > 0 && sender.send(chunk[..n].to_vec()).is_err() {
+                        break;
+                    }
+                    if kept > limit && !drain {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+    });
+
+    receiver
+}
+
+/// Gathers what a reader sent until its pipe is closed, or until the deadline
+/// if it is still open.
+fn collect(receiver: &std::sync::mpsc::Receiver<Vec<u8>>, deadline: std::time::Instant) -> Vec<u8> {
+    let mut buf = Vec::new();
+
+    while let Ok(chunk) =
+        receiver.recv_timeout(deadline.saturating_duration_since(std::time::Instant::now()))
+    {
+        buf.extend(chunk);
+    }
+
+    buf
+}
+
+/// Where the stdin of a test comes from.
+#[non_exhaustive]
+pub enum Stdin<'a> {
+    /// Redirected from a file.
+    File(std::fs::File),
+    /// Written through a pipe.
+    Bytes(&'a [u8]),
+}
+
+/// Where the stdout of a test went.
+#[non_exhaustive]
+pub enum Stdout {
+    /// The output file, `$out_dir/$key.out`.
+    File(std::path::PathBuf),
+    /// Read through a pipe.
+    Bytes(Vec<u8>),
+    /// The working directory of a test with file-based I/O, `$out_dir/$key/`,
+    /// holding its output files.
+    Dir(std::path::PathBuf),
+}
+
+/// Inputs the [`LocalRunner`](crate::checker::LocalRunner) can give to a test.
+pub trait TestInput {
+    fn stdin(&self) -> Result<Stdin<'_>, crate::checker::Error>;
+
+    /// Path of the input file, if it is one. It replaces `{input}` in the
+    /// arguments of an [`Invocation`](crate::checker::Invocation).
+    fn path(&self) -> Option<&std::path::Path> {
+        None
+    }
+}
+
+/// Outputs the [`LocalRunner`](crate::checker::LocalRunner) can collect from a
+/// test.
+pub trait TestOutput: Sized {
+    /// Whether stdout is read through a pipe rather than written in
+    /// `$out_dir/$key.out`.
+    const IN_MEMORY: bool;
+
+    fn from_stdout(stdout: Stdout) -> Result<Self, crate::checker::Error>;
+}
+
+impl TestInput for std::path::PathBuf {
+    fn stdin(&self) -> Result<Stdin<'_>, crate::checker::Error> {
+        std::fs::File::open(self)
+            .map(Stdin::File)
+            .map_err(|e| crate::checker::Error::TestError(e.to_string()))
+    }
+
+    fn path(&self) -> Option<&std::path::Path> {
+        Some(self)
+    }
+}
+
+impl TestInput for String {
+    fn stdin(&self) -> Result<Stdin<'_>, crate::checker::Error> {
+        Ok(Stdin::Bytes(self.as_bytes()))
+    }
+}
+
+impl TestInput for Vec<u8> {
+    fn stdin(&self) -> Result<Stdin<'_>, crate::checker::Error> {
+        Ok(Stdin::Bytes(self))
+    }
+}
+
+impl TestInput for bytes::Bytes {
+    fn stdin(&self) -> Result<Stdin<'_>, crate::checker::Error> {
+        Ok(Stdin::Bytes(self))
+    }
+}
+
+impl TestOutput for std::path::PathBuf {
+    const IN_MEMORY: bool = false;
+
+    fn from_stdout(stdout: Stdout) -> Result<Self, crate::checker::Error> {
+        match stdout {
+            Stdout::File(path) | Stdout::Dir(path) => Ok(path),
+            Stdout::Bytes(_) => Err(crate::checker::Error::TestError(String::from(
+                "Output was not written to a file.",
+            ))),
+        }
+    }
+}
+
+impl TestOutput for String {
+    const IN_MEMORY: bool = true;
+
+    fn from_stdout(stdout: Stdout) -> Result<Self, crate::checker::Error> {
+        let bytes = Vec::<u8>::from_stdout(stdout)?;
+
+        String::from_utf8(bytes).map_err(|_| {
+            crate::checker::Error::TestError(String::from("Output is not valid UTF-8."))
+        })
+    }
+}
+
+impl TestOutput for Vec<u8> {
+    const IN_MEMORY: bool = true;
+
+    fn from_stdout(stdout: Stdout) -> Result<Self, crate::checker::Error> {
+        match stdout {
+            Stdout::Bytes(bytes) => Ok(bytes),
+            Stdout::File(path) => {
+                std::fs::read(path).map_err(|e| crate::checker::Error::TestError(e.to_string()))
+            }
+            Stdout::Dir(_) => Err(crate::checker::Error::TestError(String::from(
+                "Output is a directory.",
+            ))),
+        }
+    }
+}
+
+impl TestOutput for bytes::Bytes {
+    const IN_MEMORY: bool = true;
+
+    fn from_stdout(stdout: Stdout) -> Result<Self, crate::checker::Error> {
+        Vec::<u8>::from_stdout(stdout).map(bytes::Bytes::from)
+    }
+}
+
+/// Waits for a test that was spawned as the leader of its own process group.
+///
+/// If the time limit is reached, the error is returned after the test is
+/// killed. Either way, the whole group gets a `SIGKILL` so that background
+/// processes (or fork bombs) can't outlive the test, and every process of the
+/// group that is a child of the checker is reaped.
+pub(crate) fn wait_and_reap(
+    mut child: std::process::Child,
+    timeout: Option<std::time::Duration>,
+) -> Result<std::process::ExitStatus, crate::checker::Error> {
+    let status = match timeout {
+        Some(duration) => match child.wait_timeout(duration) {
+            Ok(Some(status)) => Ok(status),
+            Ok(None) => Err(crate::checker::Error::TimeExceeded),
+            Err(e) => Err(crate::checker::Error::TestError(e.to_string())),
+        },
+        None => child
+            .wait()
+            .map_err(|e| crate::checker::Error::TestError(e.to_string())),
+    };
+
+    let pgid = nix::unistd::Pid::from_raw(child.id() as i32);
+
+    // Kill everything left in the group. It fails only if the group is gone.
+    let _ = nix::sys::signal::killpg(pgid, nix::sys::signal::Signal::SIGKILL);
+
+    // Reap the leader, in case it was killed above.
+    let _ = child.wait();
+
+    reap_group(pgid);
+
+    status
+}
+
+/// Reaps the processes of a group that were reparented to the checker (e.g.
+/// when it runs as PID 1 in a container). Returns once none are left.
+fn reap_group(pgid: nix::unistd::Pid) {
+    let group = nix::unistd::Pid::from_raw(-pgid.as_raw());
+
+    // Stops on ECHILD, when there is nothing left to wait for.
+    while let Ok(_) | Err(nix::errno::Errno::EINTR) = nix::sys::wait::waitpid(group, None) {}
+}
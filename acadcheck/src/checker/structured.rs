@@ -0,0 +1,287 @@
+//! Comparison of structured outputs (JSON and CSV), which are parsed whole.
+
+use crate::checker::compare::Mismatch;
+
+/// Compares two JSON documents. Numbers are equal within `tolerance`, and
+/// arrays may be in any order if `unordered_arrays` is set. The mismatch
+/// points to the JSON path of the difference.
+pub(crate) fn json_mismatch(
+    this: &[u8],
+    other: &[u8],
+    tolerance: f64,
+    unordered_arrays: bool,
+) -> Option<Mismatch> {
+    let (a, b) = match (
+        serde_json::from_slice::<serde_json::Value>(this),
+        serde_json::from_slice::<serde_json::Value>(other),
+    ) {
+        (Ok(a), Ok(b)) => (a, b),
+        (a, b) => {
+            let describe = |parsed: Result<serde_json::Value, serde_json::Error>| match parsed {
+                Ok(_) => String::from("valid JSON"),
+                Err(e) => format!("invalid JSON: {}", e),
+            };
+            return Some(mismatch(describe(a), describe(b)));
+        }
+    };
+
+    let mut path = String::from("$");
+    json_diff(&a, &b, &mut path, tolerance, unordered_arrays)
+}
+
+fn json_diff(
+    a: &serde_json::Value,
+    b: &serde_json::Value,
+    path: &mut String,
+    tolerance: f64,
+    unordered_arrays: bool,
+) -> Option<Mismatch> {
+    let at = |path: &str, a: String, b: String| {
+        Some(mismatch(
+            format!("{}: {}", path, a),
+            format!("{}: {}", path, b),
+        ))
+    };
+
+    match (a, b) {
+        (serde_json::Value::Number(x), serde_json::Value::Number(y)) => {
+            let close = match (x.as_f64(), y.as_f64()) {
+                (Some(x), Some(y)) => (x - y).abs() <= tolerance,
+                _ => x == y,
+            };
+            if close {
+                None
+            } else {
+                at(path, excerpt(a), excerpt(b))
+            }
+        }
+        (serde_json::Value::Object(x), serde_json::Value::Object(y)) => {
+            let keys = x
+                .keys()
+                .chain(y.keys())
+                .collect::<std::collections::BTreeSet<_>>();
+
+            for key in keys {
+                let len = path.len();
+                path.push_str(&format!(".{}", key));
+
+                let diff = match (x.get(key), y.get(key)) {
+                    (Some(a), Some(b)) => json_diff(a, b, path, tolerance, unordered_arrays),
+                    (a, b) => at(path, present(a), present(b)),
+                };
+
+                path.truncate(len);
+                if diff.is_some() {
+                    return diff;
+                }
+            }
+            None
+        }
+        (serde_json::Value::Array(x), serde_json::Value::Array(y)) => {
+            if x.len() != y.len() {
+                return at(
+                    path,
+                    format!("{} elements", x.len()),
+                    format!("{} elements", y.len()),
+                );
+            }
+
+            if !unordered_arrays {
+                for (i, (a, b)) in x.iter().zip(y).enumerate() {
+                    let len = path.len();
+                    path.push_str(&format!("[{}]", i));
+
+                    let diff = json_diff(a, b, path, tolerance, unordered_arrays);
+
+                    path.truncate(len);
+                    if diff.is_some() {
+                        return diff;
+                    }
+                }
+                return None;
+            }
+
+            // Match every element with an equal element that is still free.
+            let mut used = vec![false; y.len()];
+            for (i, a) in x.iter().enumerate() {
+                let found = (0..y.len()).find(|j| {
+                    !used[*j] && json_diff(a, &y[*j], &mut String::new(), tolerance, true).is_none()
+                });
+
+                match found {
+                    Some(j) => used[j] = true,
+                    None => {
+                        return at(
+                            &format!("{}[{}]", path, i),
+                            excerpt(a),
+                            String::from("[no equal element]"),
+                        );
+                    }
+                }
+            }
+            None
+        }
+        (a, b) if a == b => None,
+        (a, b) => at(path, excerpt(a), excerpt(b)),
+    }
+}
+
+fn present(value: Option<&serde_json::Value>) -> String {
+    match value {
+        Some(v) => excerpt(v),
+        None => String::from("[missing]"),
+    }
+}
+
+/// Compares two CSV documents cell by cell, ignoring the whitespace around
+/// cells. Numbers are equal within `tolerance`. With `header`, the first rows
+/// name the columns, which may then be in any order. With `unordered_rows`,
+/// the rows may be in any order. The mismatch points to the differing cell.
+pub(crate) fn csv_mismatch(
+    this: &[u8],
+    other: &[u8],
+    header: bool,
+    unordered_rows: bool,
+    tolerance: f64,
+) -> Option<Mismatch> {
+    let (mut a, mut b) = match (read_csv(this), read_csv(other)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (a, b) => {
+            let describe = |parsed: Result<Vec<Vec<String>>, csv::Error>| match parsed {
+                Ok(_) => String::from("valid CSV"),
+                Err(e) => format!("invalid CSV: {}", e),
+            };
+            return Some(mismatch(describe(a), describe(b)));
+        }
+    };
+
+    // Names of the columns, as in the reference.
+    let mut names = Vec::new();
+
+    if header && !b.is_empty() {
+        let theirs = b.remove(0);
+        let ours = if a.is_empty() {
+            Vec::new()
+        } else {
+            a.remove(0)
+        };
+
+        // Position of each reference column in the output.
+        let mut columns = Vec::new();
+        for name in &theirs {
+            match ours.iter().position(|n| n == name) {
+                Some(i) => columns.push(i),
+                None => {
+                    return Some(mismatch(
+                        format!("header: no column {:?}", name),
+                        format!("header: column {:?}", name),
+                    ));
+                }
+            }
+        }
+        if ours.len() != theirs.len() {
+            return Some(mismatch(
+                format!("header: {} columns", ours.len()),
+                format!("header: {} columns", theirs.len()),
+            ));
+        }
+
+        // Put the columns of the output in the order of the reference.
+        for row in a.iter_mut() {
+            *row = columns
+                .iter()
+                .map(|i| row.get(*i).cloned().unwrap_or_default())
+                .collect();
+        }
+        names = theirs;
+    }
+
+    let first_row = if header { 2 } else { 1 };
+    let column = |c: usize| match names.get(c) {
+        Some(name) => format!("{:?}", name),
+        None => (c + 1).to_string(),
+    };
+
+    if a.len() != b.len() {
+        return Some(mismatch(
+            format!("{} rows", a.len()),
+            format!("{} rows", b.len()),
+        ));
+    }
+
+    let cell_diff = |x: &Vec<String>, y: &Vec<String>| {
+        if x.len() != y.len() {
+            return Some(x.len().min(y.len()));
+        }
+        x.iter().zip(y).position(|(p, q)| !cell_eq(p, q, tolerance))
+    };
+
+    if unordered_rows {
+        let mut used = vec![false; b.len()];
+        for (r, row) in a.iter().enumerate() {
+            match (0..b.len()).find(|j| !used[*j] && cell_diff(row, &b[*j]).is_none()) {
+                Some(j) => used[j] = true,
+                None => {
+                    return Some(mismatch(
+                        format!("row {}: {}", r + first_row, row.join(",")),
+                        format!("row {}: [no equal row]", r + first_row),
+                    ));
+                }
+            }
+        }
+        return None;
+    }
+
+    for (r, (x, y)) in a.iter().zip(&b).enumerate() {
+        if let Some(c) = cell_diff(x, y) {
+            let cell = |row: &Vec<String>| match row.get(c) {
+                Some(v) => v.clone(),
+                None => String::from("[missing]"),
+            };
+            let at = format!("row {}, column {}", r + first_row, column(c));
+
+            return Some(mismatch(
+                format!("{}: {}", at, cell(x)),
+                format!("{}: {}", at, cell(y)),
+            ));
+        }
+    }
+
+    None
+}
+
+fn read_csv(contents: &[u8]) -> Result<Vec<Vec<String>>, csv::Error> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(contents)
+        .records()
+        .map(|r| r.map(|r| r.iter().map(String::from).collect()))
+        .collect()
+}
+
+fn cell_eq(a: &str, b: &str, tolerance: f64) -> bool {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => (x - y).abs() <= tolerance,
+        _ => a == b,
+    }
+}
+
+fn mismatch(this: String, other: String) -> Mismatch {
+    Mismatch {
+        offset: 0,
+        line: 0,
+        this,
+        other,
+    }
+}
+
+/// Up to [`CONTEXT`](crate::checker::compare::CONTEXT) bytes of a JSON value.
+fn excerpt(value: &serde_json::Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(crate::checker::compare::CONTEXT) {
+        Some((i, _)) => format!("{}...", &text[..i]),
+        None => text,
+    }
+}
@@ -0,0 +1,906 @@
+use super::*;
+
+/// Writes the contents in a file inside the directory and returns its path.
+fn write_file(dir: &tempfile::TempDir, name: &str, contents: &str) -> std::path::PathBuf {
+    let path = dir.path().join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Compares an output with its reference in memory. Returns whether they are
+/// equal, along with the excerpts of the message.
+fn compare(output: &str, reference: &str, comparison: Comparison) -> (bool, String, String) {
+    let (mut output_inner, mut ref_inner) = (String::new(), String::new());
+    let equal = String::from(output).ceq_as(
+        &String::from(reference),
+        &comparison,
+        &mut output_inner,
+        &mut ref_inner,
+    );
+    (equal, output_inner, ref_inner)
+}
+
+#[test]
+fn local_runner_redirects_io() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write_file(&dir, "1.in", "Brian was here. Briefly\n");
+
+    let runner = LocalRunner::new(dir.path().join("out"));
+    let command = vec!["cat"];
+
+    let outputs: std::collections::BTreeMap<usize, Result<std::path::PathBuf, Error>> =
+        runner.run(&command, std::collections::BTreeMap::from([(1, &input)]));
+
+    let output = outputs.get(&1).unwrap().as_ref().unwrap();
+    assert_eq!(
+        std::fs::read_to_string(output).unwrap(),
+        "Brian was here. Briefly\n"
+    );
+}
+
+#[test]
+fn local_runner_kills_process_group_on_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write_file(&dir, "1.in", "");
+    let pid_file = dir.path().join("bg.pid");
+
+    let runner = LocalRunner::new(dir.path().join("out")).with_monitors(&[MonitorType::Timeout {
+        limit: std::time::Duration::from_millis(200),
+    }]);
+    let script = format!("sleep 100 & echo $! > {}; wait", pid_file.display());
+    let command = vec!["sh", "-c", script.as_str()];
+
+    let start = std::time::Instant::now();
+    let outputs: std::collections::BTreeMap<usize, Result<std::path::PathBuf, Error>> =
+        runner.run(&command, std::collections::BTreeMap::from([(1, &input)]));
+
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert!(matches!(outputs.get(&1), Some(Err(Error::TimeExceeded))));
+
+    // The background process has been killed along with the test (it is either
+    // gone or a zombie waiting for its new parent). It may still be exiting, as
+    // the captured stderr is closed before it is done.
+    let pid = std::fs::read_to_string(&pid_file).unwrap();
+    let stat_file = format!("/proc/{}/stat", pid.trim());
+    let start = std::time::Instant::now();
+    while let Ok(stat) = std::fs::read_to_string(&stat_file) {
+        if stat.contains(") Z ") {
+            break;
+        }
+        assert!(
+            start.elapsed() < std::time::Duration::from_secs(1),
+            "{}",
+            stat
+        );
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn local_runner_limits_in_memory_outputs() {
+    let input = String::new();
+    let runner = LocalRunner::new("/nonexistent")
+        .with_monitors(&[MonitorType::Timeout {
+            limit: std::time::Duration::from_secs(5),
+        }])
+        .with_output_limit(1024);
+
+    let start = std::time::Instant::now();
+    let outputs: std::collections::BTreeMap<usize, Result<Vec<u8>, Error>> = runner.run(
+        &vec!["head", "-c", "1024", "/dev/zero"],
+        std::collections::BTreeMap::from([(1, &input)]),
+    );
+    assert_eq!(outputs[&1].as_ref().unwrap().len(), 1024);
+
+    // An endless output fails long before the timeout.
+    let outputs: std::collections::BTreeMap<usize, Result<Vec<u8>, Error>> = runner.run(
+        &vec!["yes"],
+        std::collections::BTreeMap::from([(1, &input)]),
+    );
+    assert!(matches!(outputs[&1], Err(Error::OutputLimitExceeded)));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[test]
+fn local_runner_captures_stderr() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write_file(&dir, "1.in", "");
+
+    let runner = LocalRunner::new(dir.path().join("out"));
+    let script = format!(
+        "echo 'assertion failed' >&2; head -c {} /dev/zero >&2; echo ok; exit 1",
+        2 * STDERR_LIMIT
+    );
+    let command = vec!["sh", "-c", script.as_str()];
+
+    let outputs: std::collections::BTreeMap<usize, Result<std::path::PathBuf, Error>> =
+        runner.run(&command, std::collections::BTreeMap::from([(1, &input)]));
+
+    assert!(matches!(outputs.get(&1), Some(Err(Error::TestError(_)))));
+    assert_eq!(std::fs::read_to_string(runner.out_file(1)).unwrap(), "ok\n");
+
+    let stderr = std::fs::read(runner.err_file(1)).unwrap();
+    assert_eq!(stderr.len(), STDERR_LIMIT);
+    assert!(stderr.starts_with(b"assertion failed\n"));
+}
+
+#[test]
+fn local_runner_does_not_wait_for_escaped_processes() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write_file(&dir, "1.in", "");
+    let pid_file = dir.path().join("escaped.pid");
+
+    // A stale stderr is not kept when the test can't even start.
+    let runner = LocalRunner::new(dir.path().join("out"));
+    std::fs::create_dir(dir.path().join("out")).unwrap();
+    std::fs::write(runner.err_file(1), "stale").unwrap();
+    let outputs: std::collections::BTreeMap<usize, Result<std::path::PathBuf, Error>> = runner.run(
+        &vec!["/nonexistent"],
+        std::collections::BTreeMap::from([(1, &input)]),
+    );
+    assert!(matches!(outputs.get(&1), Some(Err(Error::TestError(_)))));
+    assert!(!runner.err_file(1).exists());
+
+    // The escaped process keeps stdout and stderr open after the timeout.
+    let runner = runner.with_monitors(&[MonitorType::Timeout {
+        limit: std::time::Duration::from_millis(200),
+    }]);
+    let script = format!(
+        "setsid sleep 30 & echo $! > {}; echo started >&2; wait",
+        pid_file.display()
+    );
+    let command = vec!["sh", "-c", script.as_str()];
+
+    let start = std::time::Instant::now();
+    let outputs: std::collections::BTreeMap<usize, Result<std::path::PathBuf, Error>> =
+        runner.run(&command, std::collections::BTreeMap::from([(1, &input)]));
+
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert!(matches!(outputs.get(&1), Some(Err(Error::TimeExceeded))));
+    assert_eq!(
+        std::fs::read_to_string(runner.err_file(1)).unwrap(),
+        "started\n"
+    );
+
+    let pid = std::fs::read_to_string(&pid_file).unwrap();
+    let _ = nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(pid.trim().parse().unwrap()),
+        nix::sys::signal::Signal::SIGKILL,
+    );
+}
+
+#[test]
+fn local_runner_invokes_tests() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write_file(&dir, "1.in", "from stdin\n");
+
+    let invocations = std::collections::BTreeMap::from([(
+        1,
+        Invocation {
+            args: vec![String::from("{input}"), String::from("--fast")],
+            env: std::collections::BTreeMap::from([(String::from("MODE"), String::from("fast"))]),
+            stdin: false,
+            fixture: None,
+        },
+    )]);
+    let runner = LocalRunner::new(dir.path().join("out")).with_invocations(&invocations);
+    let command = vec!["sh", "-c", "cat && cat \"$0\" && echo \"$1 $MODE\""];
+
+    let outputs: std::collections::BTreeMap<usize, Result<std::path::PathBuf, Error>> = runner.run(
+        &command,
+        std::collections::BTreeMap::from([(1, &input), (2, &input)]),
+    );
+
+    assert_eq!(
+        std::fs::read_to_string(outputs.get(&1).unwrap().as_ref().unwrap()).unwrap(),
+        "from stdin\n--fast fast\n"
+    );
+    // Without an invocation, the script has no file to read.
+    assert!(matches!(outputs.get(&2), Some(Err(Error::TestError(_)))));
+}
+
+#[test]
+fn checker_with_local_runner() {
+    let dir = tempfile::tempdir().unwrap();
+    let in_refs = std::collections::BTreeMap::from([
+        (
+            1,
+            (
+                write_file(&dir, "1.in", "1 2\n"),
+                write_file(&dir, "1.ref", "1 2\n"),
+            ),
+        ),
+        (
+            2,
+            (
+                write_file(&dir, "2.in", "3 4\n"),
+                write_file(&dir, "2.ref", "4 3\n"),
+            ),
+        ),
+    ]);
+
+    let config = CheckerConfig {
+        monitors: vec![],
+        in_refs,
+        workers: 2,
+        pin_cpus: true,
+        ..Default::default()
+    };
+
+    let runner = LocalRunner::new(dir.path().join("out"));
+    let checker = Checker::new(config, runner.as_fn());
+    let results = checker.run_parallel(&vec!["cat"]);
+
+    assert!(matches!(results.get(&1), Some(Output::Passed)));
+    assert!(matches!(results.get(&2), Some(Output::Failed(_))));
+}
+
+#[test]
+fn checker_with_file_based_io() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write_file(&dir, "1.in", "3\n");
+    std::fs::create_dir(dir.path().join("ref")).unwrap();
+    write_file(&dir, "ref/sum.out", "6\n");
+    write_file(&dir, "ref/log.out", "done\n");
+
+    let io = IoMode::Files {
+        input: String::from("problem.in"),
+        outputs: vec![String::from("sum.out"), String::from("log.out")],
+    };
+    let results = |script: &str| {
+        let config = CheckerConfig {
+            monitors: vec![],
+            in_refs: std::collections::BTreeMap::from([(
+                1,
+                (input.clone(), dir.path().join("ref")),
+            )]),
+            io: io.clone(),
+            ..Default::default()
+        };
+
+        let runner = LocalRunner::new(dir.path().join("out")).with_io(&io);
+        Checker::new(config, runner.as_fn()).run(&vec!["sh", "-c", script])
+    };
+
+    let script = "echo $(( $(cat problem.in) * 2 )) > sum.out; echo done > log.out";
+    assert!(matches!(results(script).get(&1), Some(Output::Passed)));
+
+    // The directory is emptied before each run.
+    match results("echo 6 > sum.out").get(&1) {
+        Some(Output::Failed(message)) => assert!(message.contains("log.out: [missing]")),
+        r => panic!("{:?}", r),
+    }
+
+    // Outputs can't point to the references, directly or through a directory.
+    let script = format!(
+        "ln -s {} sum.out; echo done > log.out",
+        dir.path().join("ref/sum.out").display()
+    );
+    match results(&script).get(&1) {
+        Some(Output::Failed(message)) => {
+            assert!(
+                message.contains("sum.out: [not a regular file]"),
+                "{}",
+                message
+            )
+        }
+        r => panic!("{:?}", r),
+    }
+
+    let io = IoMode::Files {
+        input: String::from("problem.in"),
+        outputs: vec![String::from("ref/sum.out")],
+    };
+    let config = CheckerConfig {
+        monitors: vec![],
+        in_refs: std::collections::BTreeMap::from([(1, (input.clone(), dir.path().to_path_buf()))]),
+        io: io.clone(),
+        ..Default::default()
+    };
+    let runner = LocalRunner::new(dir.path().join("out")).with_io(&io);
+    let script = format!("ln -s {} ref", dir.path().join("ref").display());
+    match Checker::new(config, runner.as_fn())
+        .run(&vec!["sh", "-c", &script])
+        .get(&1)
+    {
+        Some(Output::Failed(message)) => {
+            assert!(
+                message.contains("ref/sum.out: [not a regular file]"),
+                "{}",
+                message
+            )
+        }
+        r => panic!("{:?}", r),
+    }
+
+    // Compared on their own, outputs that can't be opened say why.
+    let (mut this, mut that) = (String::new(), String::new());
+    let link = dir.path().join("link.out");
+    std::os::unix::fs::symlink(dir.path().join("1.in"), &link).unwrap();
+    assert!(!link.ceq_as(
+        &dir.path().join("none.ref"),
+        &Comparison::Lines,
+        &mut this,
+        &mut that
+    ));
+    assert_eq!(
+        (this.as_str(), that.as_str()),
+        ("[not a regular file]", "[missing]")
+    );
+}
+
+#[test]
+fn checker_compares_directory_trees() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = write_file(&dir, "1.in", "");
+    for d in ["fixture", "fixture/logs", "ref", "ref/logs", "ref/archive"] {
+        std::fs::create_dir(dir.path().join(d)).unwrap();
+    }
+    write_file(&dir, "fixture/logs/a.log", "a\n");
+    write_file(&dir, "fixture/logs/b.log", "b\n");
+    write_file(&dir, "ref/logs/b.log", "b\n");
+    write_file(&dir, "ref/archive/a.log", "a\n");
+
+    let io = IoMode::Files {
+        input: String::from("problem.in"),
+        outputs: vec![String::from("logs"), String::from("archive")],
+    };
+    let invocations = std::collections::BTreeMap::from([(
+        1,
+        Invocation {
+            fixture: Some(dir.path().join("fixture")),
+            ..Invocation::default()
+        },
+    )]);
+    let results = |script: &str| {
+        let config = CheckerConfig {
+            monitors: vec![],
+            in_refs: std::collections::BTreeMap::from([(
+                1,
+                (input.clone(), dir.path().join("ref")),
+            )]),
+            comparison: Comparison::Tree { permissions: false },
+            invocations: invocations.clone(),
+            io: io.clone(),
+            ..Default::default()
+        };
+
+        let runner = LocalRunner::new(dir.path().join("out"))
+            .with_io(&io)
+            .with_invocations(&invocations);
+        Checker::new(config, runner.as_fn()).run(&vec!["sh", "-c", script])
+    };
+
+    let script = "mkdir archive && mv logs/a.log archive/";
+    assert!(matches!(results(script).get(&1), Some(Output::Passed)));
+
+    let script = "mkdir archive && cp logs/a.log archive/ && echo c > logs/b.log";
+    match results(script).get(&1) {
+        Some(Output::Failed(message)) => {
+            assert!(message.contains("extra: a.log"));
+            assert!(
+                message.contains("differs: b.log, line 1: c instead of line 1: b"),
+                "{}",
+                message
+            );
+        }
+        r => panic!("{:?}", r),
+    }
+
+    // Symlinks are reported instead of followed, even to the reference.
+    let script = format!(
+        "mkdir archive && mv logs/a.log archive/ && rm logs/b.log && ln -s {} logs/b.log",
+        dir.path().join("ref/logs/b.log").display()
+    );
+    match results(&script).get(&1) {
+        Some(Output::Failed(message)) => {
+            assert!(
+                message.contains("not a regular file: b.log, symlink"),
+                "{}",
+                message
+            );
+        }
+        r => panic!("{:?}", r),
+    }
+}
+
+#[test]
+fn checker_skips_after_first_failure() {
+    let dir = tempfile::tempdir().unwrap();
+    let in_refs = (1..=3)
+        .map(|k| {
+            let input = write_file(&dir, &format!("{}.in", k), &format!("{}\n", k));
+            // Only the first test fails.
+            let reference = if k == 1 {
+                "wrong\n".to_string()
+            } else {
+                format!("{}\n", k)
+            };
+            (
+                k,
+                (input, write_file(&dir, &format!("{}.ref", k), &reference)),
+            )
+        })
+        .collect();
+
+    let config = CheckerConfig {
+        monitors: vec![],
+        in_refs,
+        policy: ExecutionPolicy::GroupFirstFailure {
+            groups: vec![vec![1, 2]],
+        },
+        ..Default::default()
+    };
+
+    let runner = LocalRunner::new(dir.path().join("out"));
+    let checker = Checker::new(config, runner.as_fn());
+    let results = checker.run(&vec!["cat"]);
+
+    assert!(matches!(results.get(&1), Some(Output::Failed(_))));
+    assert!(matches!(results.get(&2), Some(Output::Skipped)));
+    assert!(matches!(results.get(&3), Some(Output::Passed)));
+}
+
+#[test]
+fn config_from_toml_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_file(
+        &dir,
+        "checker.toml",
+        r#"
+# Comments are allowed.
+output_type = "none"
+workers = 2
+
+[[monitors]]
+time = { limit = { secs = 1, nanos = 0 } }
+
+[in_refs]
+1 = ["tests/in/001.in", "tests/ref/001.ref"]
+"#,
+    );
+
+    let config =
+        CheckerConfig::<std::path::PathBuf, std::path::PathBuf, Vec<MonitorType>>::from_file(&path)
+            .unwrap();
+
+    assert_eq!(config.workers, 2);
+    assert_eq!(
+        config.in_refs.get(&1).unwrap().1,
+        std::path::PathBuf::from("tests/ref/001.ref")
+    );
+}
+
+#[test]
+fn config_error_points_to_field() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_file(
+        &dir,
+        "checker.yaml",
+        "output_type: none\nworkers: many\nmonitors: []\nin_refs: {}\n",
+    );
+
+    let error =
+        CheckerConfig::<std::path::PathBuf, std::path::PathBuf, Vec<MonitorType>>::from_file(&path)
+            .unwrap_err()
+            .to_string();
+
+    assert!(error.contains("`workers`"), "{}", error);
+    assert!(error.contains("line 2"), "{}", error);
+}
+
+#[test]
+fn discovery_pairs_files_by_name() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["test1", "test2", "test10"] {
+        std::fs::create_dir_all(dir.path().join("in").join(name)).unwrap();
+        std::fs::create_dir_all(dir.path().join("ref").join(name)).unwrap();
+        write_file(&dir, &format!("in/{}/input.txt", name), name);
+        write_file(&dir, &format!("ref/{}/output.txt", name), name);
+    }
+
+    let in_refs = Discovery::new(dir.path().join("in"), dir.path().join("ref"))
+        .with_patterns("*/input.txt", "*/output.txt")
+        .discover()
+        .unwrap();
+
+    assert_eq!(in_refs.len(), 3);
+    assert_eq!(
+        in_refs.get(&3).unwrap().0,
+        dir.path().join("in/test10/input.txt")
+    );
+}
+
+#[test]
+fn discovery_reports_unmatched_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("in")).unwrap();
+    std::fs::create_dir_all(dir.path().join("ref")).unwrap();
+    write_file(&dir, "in/001.in", "");
+    write_file(&dir, "in/002.in", "");
+    write_file(&dir, "ref/001.ok", "");
+
+    let error = Discovery::new(dir.path().join("in"), dir.path().join("ref"))
+        .with_patterns("*.in", "*.ok")
+        .discover()
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("002.in"), "{}", error);
+}
+
+#[test]
+fn checker_with_in_memory_tests() {
+    let in_refs = std::collections::BTreeMap::from([
+        (1, (String::from("1 2\n"), String::from("1 2\n"))),
+        (2, (String::from("3 4\n"), String::from("4 3\n"))),
+    ]);
+
+    let config = CheckerConfig {
+        monitors: vec![],
+        in_refs,
+        workers: 2,
+        ..Default::default()
+    };
+
+    // Nothing is written in the output directory.
+    let runner = LocalRunner::new("/nonexistent");
+    let checker = Checker::new(config, runner.as_fn());
+    let results = checker.run_parallel(&vec!["cat"]);
+
+    assert!(matches!(results.get(&1), Some(Output::Passed)));
+    assert!(matches!(results.get(&2), Some(Output::Failed(_))));
+}
+
+#[test]
+fn checker_runs_tests_in_order_with_any_runner() {
+    let in_refs = std::collections::BTreeMap::from([
+        (1, (String::from("1\n"), String::from("1\n"))),
+        (2, (String::from("2\n"), String::from("2\n"))),
+    ]);
+
+    let config = CheckerConfig {
+        monitors: vec![],
+        in_refs,
+        ..Default::default()
+    };
+
+    // A runner that can't be shared between threads.
+    let order = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let runner = |_: &Vec<&str>, inputs: std::collections::BTreeMap<usize, &String>| {
+        inputs
+            .into_iter()
+            .map(|(key, input)| {
+                order.borrow_mut().push(key);
+                (key, Ok(input.clone()))
+            })
+            .collect()
+    };
+
+    let results = Checker::new(config, runner).run(&vec!["cat"]);
+
+    assert!(matches!(results.get(&1), Some(Output::Passed)));
+    assert!(matches!(results.get(&2), Some(Output::Passed)));
+    assert_eq!(*order.borrow(), vec![1, 2]);
+}
+
+#[test]
+fn bytes_compare_with_invalid_utf8() {
+    let (mut output_inner, mut ref_inner) = (String::new(), String::new());
+    let output = bytes::Bytes::from_static(b"\xff\n");
+
+    assert!(!output.ceq(
+        &bytes::Bytes::from_static(b"\xfe\n"),
+        &mut output_inner,
+        &mut ref_inner
+    ));
+    assert_eq!(output_inner, "line 1: \u{fffd}");
+}
+
+#[test]
+fn large_files_compare_with_excerpts() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // A single line much longer than the buffers, differing near its end.
+    let line = "7 ".repeat(1 << 20);
+    let output = write_file(&dir, "1.out", &format!("ok\n{}8\n", line));
+    let reference = write_file(&dir, "1.ref", &format!("ok\n{}9\n", line));
+
+    let (mut output_inner, mut ref_inner) = (String::new(), String::new());
+    assert!(!output.ceq(&reference, &mut output_inner, &mut ref_inner));
+
+    assert!(output_inner.starts_with("line 2: ..."), "{}", output_inner);
+    assert!(output_inner.ends_with("7 8"), "{}", output_inner);
+    assert!(ref_inner.ends_with("7 9"), "{}", ref_inner);
+    assert!(output_inner.len() < 2 * compare::CONTEXT);
+}
+
+#[test]
+fn constraints_reject_invalid_inputs() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let constraints = Constraints {
+        min_lines: Some(2),
+        max_lines: Some(2),
+        lines: vec![
+            LineConstraint {
+                at: vec![1],
+                integers: Some(crate::checker::IntegerRange { min: 1, max: 100 }),
+                ..Default::default()
+            },
+            LineConstraint {
+                at: vec![2],
+                regex: Some(String::from("[a-z]+")),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let valid = write_file(&dir, "1.in", "5 100\nabc\n");
+    assert_eq!(constraints.check(valid), Ok(()));
+
+    let cases = [
+        (
+            "5 101\nabc\n",
+            "line 1: \"101\" is not an integer in [1, 100]",
+        ),
+        ("5\nABC\n", "line 2: does not match `^(?:[a-z]+)$`"),
+        ("5\nabc", "missing trailing newline"),
+        ("5\n", "1 lines, expected at least 2"),
+    ];
+
+    for (contents, reason) in cases {
+        let input = write_file(&dir, "2.in", contents);
+        assert_eq!(constraints.check(input), Err(String::from(reason)));
+    }
+}
+
+#[test]
+fn checker_accepts_any_reference() {
+    let in_refs = std::collections::BTreeMap::from([
+        (1, (String::from("3 4\n"), String::from("4 3\n"))),
+        (2, (String::from("1 2 3\n"), String::from("9\n"))),
+    ]);
+    let alternatives = std::collections::BTreeMap::from([
+        (1, vec![String::from("3 4\n")]),
+        (2, vec![String::from("1 2 4\n"), String::from("1\n")]),
+    ]);
+
+    let config = CheckerConfig {
+        monitors: vec![],
+        in_refs,
+        alternatives,
+        ..Default::default()
+    };
+
+    let runner = LocalRunner::new("/nonexistent");
+    let checker = Checker::new(config, runner.as_fn());
+    let results = checker.run(&vec!["cat"]);
+
+    assert!(matches!(results.get(&1), Some(Output::Passed)));
+    match results.get(&2) {
+        Some(Output::Failed(message)) => {
+            assert!(message.contains("Expected: line 1: 1 2 4"), "{}", message);
+            assert!(message.contains("Closest reference: 2 of 3"), "{}", message);
+        }
+        o => panic!("{:?}", o),
+    }
+
+    // References in files are named by their path.
+    let dir = tempfile::tempdir().unwrap();
+    let in_refs = std::collections::BTreeMap::from([(
+        1,
+        (
+            write_file(&dir, "1.in", "1 2 3\n"),
+            write_file(&dir, "1.ref", "9\n"),
+        ),
+    )]);
+    let alternatives =
+        std::collections::BTreeMap::from([(1, vec![write_file(&dir, "1.alt", "1 2 4\n")])]);
+
+    let config = CheckerConfig {
+        monitors: vec![],
+        in_refs,
+        alternatives,
+        ..Default::default()
+    };
+
+    let runner = LocalRunner::new(dir.path().join("out"));
+    let checker = Checker::new(config, runner.as_fn());
+
+    match checker.run(&vec!["cat"]).get(&1) {
+        Some(Output::Failed(message)) => {
+            let closest = format!(
+                "Closest reference: {}\n",
+                dir.path().join("1.alt").display()
+            );
+            assert!(message.contains(&closest), "{}", message);
+        }
+        o => panic!("{:?}", o),
+    }
+}
+
+#[test]
+fn unordered_comparisons() {
+    assert!(compare("b\na\na\n", "a\nb\na\n", Comparison::Lines).0);
+    assert_eq!(
+        compare("b\na\n", "a\nb\na\n", Comparison::Lines),
+        (false, String::from("1 x a"), String::from("2 x a"))
+    );
+
+    assert!(compare("2 1\n3\n", "1 2 3\n", Comparison::Tokens).0);
+    assert!(!compare("2 1\n", "1 2 3\n", Comparison::Tokens).0);
+
+    assert!(compare("2\n1\n\n4\n3\n", "1\n2\n\n3\n4\n", Comparison::SortedBlocks).0);
+    assert_eq!(
+        compare("2\n1\n3\n\n4\n", "1\n2\n\n3\n4\n", Comparison::SortedBlocks),
+        (
+            false,
+            String::from("block 1: 3"),
+            String::from("block 1: [EOF]")
+        )
+    );
+    // Lines don't move between blocks.
+    assert!(!compare("1\n\n2\n", "2\n\n1\n", Comparison::SortedBlocks).0);
+}
+
+#[test]
+fn structured_comparisons() {
+    let json = Comparison::Json {
+        tolerance: 1e-6,
+        unordered_arrays: false,
+    };
+    assert!(
+        compare(
+            r#"{"b": [1.0, 2], "a": 0.1000001}"#,
+            r#"{"a":0.1,"b":[1,2]}"#,
+            json
+        )
+        .0
+    );
+    assert_eq!(
+        compare(r#"{"a": {"b": [1, 3]}}"#, r#"{"a": {"b": [1, 2]}}"#, json),
+        (
+            false,
+            String::from("$.a.b[1]: 3"),
+            String::from("$.a.b[1]: 2")
+        )
+    );
+
+    let unordered = Comparison::Json {
+        tolerance: 0.0,
+        unordered_arrays: true,
+    };
+    assert!(compare("[[2, 1], 3]", "[3, [1, 2]]", unordered).0);
+
+    let csv = Comparison::Csv {
+        header: true,
+        unordered_rows: false,
+        tolerance: 0.0,
+    };
+    assert!(compare("name, age\nana, 20.0\n", "age,name\n20,ana\n", csv).0);
+    assert_eq!(
+        compare(
+            "name,age\nana,20\nion,30\n",
+            "name,age\nana,20\nion,31\n",
+            csv
+        ),
+        (
+            false,
+            String::from("row 3, column \"age\": 30"),
+            String::from("row 3, column \"age\": 31")
+        )
+    );
+
+    let unordered = Comparison::Csv {
+        header: false,
+        unordered_rows: true,
+        tolerance: 0.0,
+    };
+    assert!(compare("b,2\na,1\n", "a,1\nb,2\n", unordered).0);
+}
+
+#[test]
+fn pattern_comparisons() {
+    let reference = "Started\nre:\\d{2}:\\d{2}\nre:0x[0-9a-f]+\n";
+    assert!(
+        compare(
+            "Started\n12:30\n0xdeadbeef\n",
+            reference,
+            Comparison::Patterns
+        )
+        .0
+    );
+    assert_eq!(
+        compare("Started\n12:30\n0xg\n", reference, Comparison::Patterns),
+        (
+            false,
+            String::from("line 3: 0xg"),
+            String::from("line 3: re:0x[0-9a-f]+")
+        )
+    );
+    assert!(!compare("Started\n12:30\n", reference, Comparison::Patterns).0);
+
+    assert!(compare("Hello, Ana!\n", "Hello, \\w+!\n", Comparison::Regex).0);
+    assert!(!compare("Hello, Ana!\nBye\n", "Hello, \\w+!\n", Comparison::Regex).0);
+}
+
+#[test]
+fn checker_flags_unstable_tests() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // Runs `odd` on every other run, and `even` on the others.
+    let counter = dir.path().join("runs");
+    let script = |odd: &str, even: &str| {
+        format!(
+            "n=$(cat {0} 2>/dev/null || echo 0); echo $((n + 1)) > {0}; \
+             if [ $((n % 2)) = 0 ]; then {1}; else {2}; fi",
+            counter.display(),
+            odd,
+            even
+        )
+    };
+
+    let in_refs = std::collections::BTreeMap::from([(1, (String::new(), String::from("0\n")))]);
+
+    let results = |script: String, decision| {
+        let _ = std::fs::remove_file(&counter);
+        let config = CheckerConfig {
+            monitors: vec![],
+            in_refs: in_refs.clone(),
+            repeats: 3,
+            decision,
+            ..Default::default()
+        };
+
+        let runner = LocalRunner::new("/nonexistent").with_monitors(&[MonitorType::Timeout {
+            limit: std::time::Duration::from_millis(200),
+        }]);
+        Checker::new(config, runner.as_fn()).run(&vec!["sh", "-c", &script])
+    };
+
+    let verdicts = |runs: &[Run]| runs.iter().map(|r| r.verdict).collect::<Vec<_>>();
+
+    match results(script("echo 0", "echo 1"), Decision::Worst).get(&1) {
+        Some(Output::Unstable { verdict, runs }) => {
+            assert!(matches!(**verdict, Output::Failed(_)));
+            assert_eq!(
+                verdicts(runs),
+                [Verdict::Passed, Verdict::WrongAnswer, Verdict::Passed]
+            );
+        }
+        o => panic!("{:?}", o),
+    }
+
+    match results(script("echo 0", "echo 1"), Decision::Majority).get(&1) {
+        Some(Output::Unstable { verdict, .. }) => {
+            assert!(matches!(**verdict, Output::Passed));
+        }
+        o => panic!("{:?}", o),
+    }
+
+    // Runs that fail in different ways disagree too.
+    match results(script("echo 1", "sleep 5"), Decision::Worst).get(&1) {
+        Some(Output::Unstable { runs, .. }) => {
+            assert_eq!(
+                verdicts(runs),
+                [
+                    Verdict::WrongAnswer,
+                    Verdict::TimeExceeded,
+                    Verdict::WrongAnswer
+                ]
+            );
+        }
+        o => panic!("{:?}", o),
+    }
+
+    // Every run is kept when they agree.
+    match results(script("echo 0", "echo 0"), Decision::Worst).get(&1) {
+        Some(Output::Repeated { verdict, runs }) => {
+            assert!(matches!(**verdict, Output::Passed));
+            assert_eq!(verdicts(runs), [Verdict::Passed; 3]);
+        }
+        o => panic!("{:?}", o),
+    }
+}
@@ -0,0 +1,187 @@
+//! Comparison of directory trees, by file list, contents and permissions.
+
+use crate::checker::compare::Mismatch;
+
+/// Differences listed at most in a report.
+const REPORT_LIMIT: usize = 20;
+
+/// An entry of a directory tree.
+struct Entry {
+    kind: Kind,
+    mode: u32,
+}
+
+/// Kind of an entry. Symlinks are not followed.
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    File,
+    Directory,
+    Symlink,
+    Special,
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Kind::File => "file",
+            Kind::Directory => "directory",
+            Kind::Symlink => "symlink",
+            Kind::Special => "special file",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Compares the trees under two directories. The mismatch lists the missing,
+/// extra and differing files, relative to the directories. Symlinks and
+/// special files in this tree are never opened, and always differ.
+pub(crate) fn tree_mismatch(
+    this: &std::path::Path,
+    other: &std::path::Path,
+    permissions: bool,
+) -> std::io::Result<Option<Mismatch>> {
+    let (mut a, mut b) = (
+        std::collections::BTreeMap::new(),
+        std::collections::BTreeMap::new(),
+    );
+    list(this, std::path::Path::new(""), &mut a)?;
+    list(other, std::path::Path::new(""), &mut b)?;
+
+    let paths = a
+        .keys()
+        .chain(b.keys())
+        .collect::<std::collections::BTreeSet<_>>();
+
+    let mut differences = Vec::new();
+
+    for path in paths {
+        let (found, expected) = match (a.get(path), b.get(path)) {
+            (Some(found), Some(expected)) => (found, expected),
+            (None, _) => {
+                differences.push(format!("missing: {}", path.display()));
+                continue;
+            }
+            (_, None) => {
+                differences.push(format!("extra: {}", path.display()));
+                continue;
+            }
+        };
+
+        if let Kind::Symlink | Kind::Special = found.kind {
+            differences.push(format!(
+                "not a regular file: {}, {}",
+                path.display(),
+                found.kind
+            ));
+            continue;
+        }
+
+        if found.kind != expected.kind {
+            differences.push(format!(
+                "differs: {}, {} instead of {}",
+                path.display(),
+                found.kind,
+                expected.kind
+            ));
+            continue;
+        }
+
+        if found.kind == Kind::File {
+            let (f, g) = (
+                crate::checker::config::open_output(&this.join(path))?,
+                std::fs::File::open(other.join(path))?,
+            );
+
+            if let Some(m) = crate::checker::compare::first_mismatch(
+                std::io::BufReader::new(f),
+                std::io::BufReader::new(g),
+            )? {
+                differences.push(format!(
+                    "differs: {}, {} instead of {}",
+                    path.display(),
+                    m.this,
+                    m.other
+                ));
+                continue;
+            }
+        }
+
+        if permissions && found.mode != expected.mode {
+            differences.push(format!(
+                "permissions: {}, {:o} instead of {:o}",
+                path.display(),
+                found.mode,
+                expected.mode
+            ));
+        }
+    }
+
+    if differences.is_empty() {
+        return Ok(None);
+    }
+
+    let count = differences.len();
+    differences.truncate(REPORT_LIMIT);
+    if count > REPORT_LIMIT {
+        differences.push(format!("... and {} more", count - REPORT_LIMIT));
+    }
+
+    Ok(Some(Mismatch {
+        offset: 0,
+        line: 0,
+        this: differences.join("\n"),
+        other: format!("directory tree {:?}", other),
+    }))
+}
+
+/// Lists the entries under `root.join(relative)`, by their path relative to
+/// `root`.
+fn list(
+    root: &std::path::Path,
+    relative: &std::path::Path,
+    entries: &mut std::collections::BTreeMap<std::path::PathBuf, Entry>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(root.join(relative))? {
+        let entry = entry?;
+        let path = relative.join(entry.file_name());
+        let metadata = std::fs::symlink_metadata(root.join(&path))?;
+        let file_type = metadata.file_type();
+
+        let kind = if file_type.is_symlink() {
+            Kind::Symlink
+        } else if file_type.is_dir() {
+            Kind::Directory
+        } else if file_type.is_file() {
+            Kind::File
+        } else {
+            Kind::Special
+        };
+
+        entries.insert(
+            path.clone(),
+            Entry {
+                kind,
+                mode: mode(&metadata),
+            },
+        );
+
+        if kind == Kind::Directory {
+            list(root, &path, entries)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn mode(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode() & 0o7777
+}
+
+/// Permissions are only compared on Unix.
+#[cfg(not(unix))]
+fn mode(_metadata: &std::fs::Metadata) -> u32 {
+    0
+}
@@ -0,0 +1,185 @@
+//! Validation of test inputs, before they are used for judging.
+
+/// Declarative constraints on the inputs of a problem.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Constraints {
+    pub min_lines: Option<usize>,
+    pub max_lines: Option<usize>,
+    /// Whether the input must end with a newline. Defaults to true.
+    #[cfg_attr(feature = "use-serde", serde(default = "default_trailing_newline"))]
+    pub trailing_newline: bool,
+    /// Constraints on the content of the lines.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub lines: Vec<LineConstraint>,
+}
+
+#[cfg(feature = "use-serde")]
+fn default_trailing_newline() -> bool {
+    true
+}
+
+/// Constraints on the lines given by `at` (numbered from 1), or on every line
+/// if `at` is empty.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineConstraint {
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub at: Vec<usize>,
+    /// Regex the whole line must match.
+    pub regex: Option<String>,
+    /// Range every whitespace-separated token of the line must be an integer
+    /// in.
+    pub integers: Option<IntegerRange>,
+}
+
+/// Inclusive range of integers.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegerRange {
+    pub min: i64,
+    pub max: i64,
+}
+
+/// Constraints that only require a trailing newline.
+impl Default for Constraints {
+    fn default() -> Self {
+        Self {
+            min_lines: None,
+            max_lines: None,
+            trailing_newline: true,
+            lines: Vec::new(),
+        }
+    }
+}
+
+impl Constraints {
+    /// Checks an input line by line, without reading it whole. Returns the
+    /// first constraint it violates.
+    pub fn check<P>(&self, input: P) -> Result<(), String>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let regexes = self
+            .lines
+            .iter()
+            .map(|l| {
+                l.regex
+                    .as_ref()
+                    .map(|r| regex::Regex::new(&format!("^(?:{})$", r)))
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        let file = std::fs::File::open(input.as_ref()).map_err(|e| e.to_string())?;
+        let mut reader = std::io::BufReader::new(file);
+
+        let mut count = 0;
+        let mut line = Vec::new();
+        let mut ends_with_newline = true;
+
+        loop {
+            line.clear();
+            let read = std::io::BufRead::read_until(&mut reader, b'\n', &mut line)
+                .map_err(|e| e.to_string())?;
+            if read == 0 {
+                break;
+            }
+
+            count += 1;
+            ends_with_newline = line.last() == Some(&b'\n');
+            if ends_with_newline {
+                line.pop();
+            }
+
+            let text = match std::str::from_utf8(&line) {
+                Ok(t) => t,
+                Err(_) => {
+                    return Err(format!("line {}: not valid UTF-8", count));
+                }
+            };
+
+            for (constraint, regex) in self.lines.iter().zip(&regexes) {
+                if !constraint.at.is_empty() && !constraint.at.contains(&count) {
+                    continue;
+                }
+
+                if let Some(regex) = regex {
+                    if !regex.is_match(text) {
+                        return Err(format!(
+                            "line {}: does not match `{}`",
+                            count,
+                            regex.as_str()
+                        ));
+                    }
+                }
+
+                if let Some(range) = constraint.integers {
+                    for token in text.split_whitespace() {
+                        match token.parse::<i64>() {
+                            Ok(n) if range.min <= n && n <= range.max => {}
+                            _ => {
+                                return Err(format!(
+                                    "line {}: {:?} is not an integer in [{}, {}]",
+                                    count, token, range.min, range.max
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.trailing_newline && !ends_with_newline {
+            return Err(String::from("missing trailing newline"));
+        }
+
+        if let Some(min) = self.min_lines {
+            if count < min {
+                return Err(format!("{} lines, expected at least {}", count, min));
+            }
+        }
+
+        if let Some(max) = self.max_lines {
+            if count > max {
+                return Err(format!("{} lines, expected at most {}", count, max));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks an input with a validator command, which reads the input from stdin
+/// and exits with a non-zero status if it is invalid. The first line of its
+/// stderr is the reason.
+pub fn check_with<S, P>(command: &[S], input: P) -> Result<(), String>
+where
+    S: AsRef<std::ffi::OsStr>,
+    P: AsRef<std::path::Path>,
+{
+    if command.is_empty() {
+        return Err(String::from("Empty validator command."));
+    }
+
+    let stdin = std::fs::File::open(input.as_ref()).map_err(|e| e.to_string())?;
+
+    let output = std::process::Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(stdin)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.lines().next() {
+        Some(reason) if !reason.trim().is_empty() => Err(String::from(reason.trim())),
+        _ => Err(format!("Validator exit status: {}", output.status)),
+    }
+}
@@ -18,15 +18,35 @@ pub enum SupportedGccLanguage {
     Go,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gcc {
     language: SupportedGccLanguage,
+    #[cfg_attr(
+        feature = "use-serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    harness: Option<crate::language::Harness>,
 }
 
 impl Gcc {
     pub fn new(language: SupportedGccLanguage) -> Self {
-        Self { language }
+        Self {
+            language,
+            harness: None,
+        }
+    }
+
+    /// Compiles the solution along with a harness that owns `main`. Renaming
+    /// the `main` of the solution only works for C and C++.
+    pub fn with_harness(mut self, harness: crate::language::Harness) -> Self {
+        self.harness = Some(harness);
+        self
+    }
+
+    /// The language compiled.
+    pub fn language(&self) -> SupportedGccLanguage {
+        self.language
     }
 }
 
@@ -58,18 +78,7 @@ impl crate::language::Compiler for Gcc {
         I: IntoIterator<Item = S>,
     {
         // Get source os string to pass into command.
-        let source = match source {
-            crate::solution::Source::File(file) => std::ffi::OsString::from(file.to_str().unwrap()),
-            crate::solution::Source::Directory(dir) => {
-                std::ffi::OsString::from(format!("{}/*", dir.to_str().unwrap()))
-            }
-            crate::solution::Source::Regex { regex } => {
-                std::ffi::OsString::from(format!("{}", regex.as_str()))
-            }
-            _ => {
-                panic!("Source type not supported yet!")
-            }
-        };
+        let argument = source.argument();
 
         // Destination file (executable).
         let dest = exec.to_str().unwrap();
@@ -97,6 +106,15 @@ impl crate::language::Compiler for Gcc {
             }
         };
 
+        // Build along with the harness, which owns main.
+        if let Some(harness) = &self.harness {
+            let flags = match flags {
+                Some(f) => f.into_iter().map(|f| OsString::from(f.as_ref())).collect(),
+                None => Vec::new(),
+            };
+            return build_with_harness(exec_without_path, lang, flags, source, exec, harness);
+        }
+
         // Build command.
         let mut compile_command = std::process::Command::new(exec_without_path);
 
@@ -108,7 +126,7 @@ impl crate::language::Compiler for Gcc {
         let compile_command = compile_command
             .arg("-x")
             .arg(lang)
-            .arg(source)
+            .arg(argument)
             .arg("-o")
             .arg(dest)
             .stderr(std::process::Stdio::null())
@@ -150,3 +168,141 @@ impl crate::language::Compiler for Gcc {
         }
     }
 }
+
+/// Compiles each file of the solution to an object, checking or renaming its
+/// `main`, then links them with the sources of the harness. Returns the
+/// command of the executable.
+fn build_with_harness(
+    compiler: OsString,
+    lang: OsString,
+    flags: Vec<OsString>,
+    source: &crate::solution::Source,
+    exec: std::path::PathBuf,
+    harness: &crate::language::Harness,
+) -> Result<Vec<OsString>, crate::language::Error> {
+    // Directories of the headers.
+    let includes = harness
+        .headers
+        .iter()
+        .filter_map(|h| h.parent())
+        .flat_map(|d| [OsString::from("-I"), OsString::from(d)])
+        .collect::<Vec<_>>();
+
+    let mut objects = Vec::new();
+
+    for (i, file) in source_files(source)?.iter().enumerate() {
+        let object = exec.with_extension(format!("{}.o", i));
+
+        let mut compile = std::process::Command::new(&compiler);
+        compile
+            .args(&flags)
+            .args(&includes)
+            .arg("-x")
+            .arg(&lang)
+            .arg("-c")
+            .arg(file)
+            .arg("-o")
+            .arg(&object);
+        if let crate::language::MainPolicy::Rename(name) = &harness.main {
+            compile.arg(format!("-Dmain={}", name));
+        }
+        run_compiler(&mut compile)?;
+
+        if let crate::language::MainPolicy::Reject = harness.main {
+            if defines_main(&object)? {
+                return Err(crate::language::Error::MainDefined);
+            }
+        }
+
+        objects.push(object);
+    }
+
+    let mut link = std::process::Command::new(&compiler);
+    link.args(&flags)
+        .args(&includes)
+        .arg("-x")
+        .arg(&lang)
+        .args(&harness.sources)
+        .arg("-x")
+        .arg("none")
+        .args(&objects)
+        .arg("-o")
+        .arg(&exec);
+    run_compiler(&mut link)?;
+
+    match exec.canonicalize() {
+        Ok(binary) => Ok(vec![OsString::from(binary)]),
+        Err(_) => Err(crate::language::Error::CompilationFailed(String::from(
+            "Building path to executable received an error.",
+        ))),
+    }
+}
+
+/// Source files of the solution: the file, or the files of the directory
+/// (like `dir/*`) but its headers, which are only included.
+fn source_files(
+    source: &crate::solution::Source,
+) -> Result<Vec<std::path::PathBuf>, crate::language::Error> {
+    let dir = match source {
+        crate::solution::Source::Directory(dir) => dir,
+        s => return Ok(vec![std::path::PathBuf::from(s.argument())]),
+    };
+    let error =
+        |e: std::io::Error| crate::language::Error::CompilationFailed(format!("{:?}: {}", dir, e));
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(error)? {
+        let path = entry.map_err(error)?.path();
+        let header = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("h" | "hh" | "hpp" | "hxx")
+        );
+
+        if path.is_file() && !header {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Runs a compiler command, failing on a non-zero exit status with what the
+/// compiler wrote on stderr.
+fn run_compiler(command: &mut std::process::Command) -> Result<(), crate::language::Error> {
+    let output = command
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .output()
+        .map_err(|e| {
+            crate::language::Error::CompilationFailed(format!(
+                "{}. (The compiler might not be in your PATH.)",
+                e
+            ))
+        })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(crate::language::Error::CompilationFailed(format!(
+            "{}\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )))
+    }
+}
+
+/// Whether an object file defines a `main` symbol, listed by `nm`.
+fn defines_main(object: &std::path::Path) -> Result<bool, crate::language::Error> {
+    let output = std::process::Command::new("nm")
+        .arg("--defined-only")
+        .arg("-P")
+        .arg(object)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .map_err(|e| crate::language::Error::CompilationFailed(e.to_string()))?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .any(|l| l.split_whitespace().next() == Some("main")))
+}
@@ -18,6 +18,45 @@ pub enum Error {
     /// information about the failing.
     #[error("Make build failed: {0}")]
     MakefileBuildFailed(String),
+
+    /// The solution has its own `main`, while the
+    /// [`Harness`](crate::language::Harness) owns it.
+    #[error("The solution defines main, which belongs to the harness.")]
+    MainDefined,
+}
+
+/// Sources of a problem that own `main` ("grader stubs"). The solution only
+/// implements the functions the harness calls.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Harness {
+    /// Sources compiled along with the solution. For interpreted languages,
+    /// the first one is run, and the solution can be imported as `solution`.
+    pub sources: Vec<std::path::PathBuf>,
+    /// Headers of the harness. Their directories are searched for includes.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub headers: Vec<std::path::PathBuf>,
+    /// What happens if the solution has its own `main`.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub main: MainPolicy,
+}
+
+/// What happens to the `main` of a solution built with a
+/// [`Harness`](crate::language::Harness).
+#[non_exhaustive]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MainPolicy {
+    /// The solution is rejected with
+    /// [`MainDefined`](crate::language::Error::MainDefined).
+    #[default]
+    #[cfg_attr(feature = "use-serde", serde(rename = "reject"))]
+    Reject,
+    /// The `main` of the solution is renamed, so the harness may call it. Only
+    /// C and C++ solutions can be renamed. Python ones are imported without
+    /// running their `main` block, and their harness can't rename it.
+    #[cfg_attr(feature = "use-serde", serde(rename = "rename"))]
+    Rename(String),
 }
 
 /// Trait for language processors such as compilers or interpreters.
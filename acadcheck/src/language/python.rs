@@ -5,12 +5,50 @@ use super::Interpreter;
 #[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Python {
     version: Option<String>,
+    #[cfg_attr(
+        feature = "use-serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "harness"
+        )
+    )]
+    harness: Option<crate::language::Harness>,
 }
 
+/// Runs the harness given as the second argument, after importing the
+/// solution given as the first one as the `solution` module. The arguments
+/// that follow are the ones of the harness.
+const HARNESS_BOOTSTRAP: &str = "\
+import importlib.util, os, runpy, sys
+solution, harness = sys.argv[1:3]
+sys.path[:0] = [os.path.dirname(os.path.abspath(p)) for p in (harness, solution)]
+spec = importlib.util.spec_from_file_location('solution', solution)
+sys.modules['solution'] = importlib.util.module_from_spec(spec)
+spec.loader.exec_module(sys.modules['solution'])
+sys.argv = sys.argv[2:]
+runpy.run_path(harness, run_name='__main__')
+";
+
 impl Python {
     /// Returns a Python instance without a version.
     pub fn new() -> Self {
-        return Self { version: None };
+        return Self {
+            version: None,
+            harness: None,
+        };
+    }
+
+    /// Runs the first source of a harness instead of the solution, which is
+    /// imported as the `solution` module, so its `main` block doesn't run. If
+    /// the harness renames `main`, it will return an error.
+    pub fn with_harness(
+        mut self,
+        harness: crate::language::Harness,
+    ) -> Result<Self, anyhow::Error> {
+        check_harness(&harness).map_err(|e| anyhow::format_err!(e))?;
+        self.harness = Some(harness);
+        Ok(self)
     }
 
     /// Adds a version. If the format is invalid, it will return an error.
@@ -30,6 +68,31 @@ impl Python {
     }
 }
 
+/// Python solutions are imported, so there is no `main` to rename.
+fn check_harness(harness: &crate::language::Harness) -> Result<(), &'static str> {
+    match harness.main {
+        crate::language::MainPolicy::Rename(_) => {
+            Err("The main of a Python solution can't be renamed, only rejected.")
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "use-serde")]
+fn harness<'de, D>(deserializer: D) -> Result<Option<crate::language::Harness>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let harness =
+        <Option<crate::language::Harness> as serde::Deserialize>::deserialize(deserializer)?;
+
+    if let Some(harness) = &harness {
+        check_harness(harness).map_err(serde::de::Error::custom)?;
+    }
+
+    Ok(harness)
+}
+
 impl crate::language::LanguageProcessor for Python {
     fn run(
         &self,
@@ -37,6 +100,19 @@ impl crate::language::LanguageProcessor for Python {
         source: crate::solution::Source,
         _exec: Option<std::path::PathBuf>,
     ) -> Result<Vec<std::ffi::OsString>, crate::language::Error> {
+        // A main block of the solution is rejected.
+        if let (Some(harness), crate::solution::Source::File(file)) = (&self.harness, &source) {
+            if let crate::language::MainPolicy::Reject = harness.main {
+                let main = regex::Regex::new(r#"__name__\s*==\s*['"]__main__['"]"#).unwrap();
+                let code = std::fs::read_to_string(file)
+                    .map_err(|e| crate::language::Error::CompilationFailed(e.to_string()))?;
+
+                if main.is_match(&code) {
+                    return Err(crate::language::Error::MainDefined);
+                }
+            }
+        }
+
         Ok(self.run_interpreted(args, source))
     }
 }
@@ -58,18 +134,7 @@ impl crate::language::Interpreter for Python {
         };
 
         // Get source os string to pass into command.
-        let source = match source {
-            crate::solution::Source::File(file) => std::ffi::OsString::from(file.to_str().unwrap()),
-            crate::solution::Source::Directory(dir) => {
-                std::ffi::OsString::from(format!("{}/*", dir.to_str().unwrap()))
-            }
-            crate::solution::Source::Regex { regex } => {
-                std::ffi::OsString::from(format!("{}", regex.as_str()))
-            }
-            _ => {
-                panic!("Source type not supported yet!")
-            }
-        };
+        let source = source.argument();
 
         // Build command vector.
         let mut command: Vec<std::ffi::OsString> = Vec::new();
@@ -80,6 +145,16 @@ impl crate::language::Interpreter for Python {
                 command.push(f);
             });
         }
+
+        // The harness runs and imports the solution.
+        if let Some(harness) = self.harness.as_ref().and_then(|h| h.sources.first()) {
+            command.push(std::ffi::OsString::from("-c"));
+            command.push(std::ffi::OsString::from(HARNESS_BOOTSTRAP));
+            command.push(source);
+            command.push(std::ffi::OsString::from(harness));
+            return command;
+        }
+
         command.push(source);
 
         command
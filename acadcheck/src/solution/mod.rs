@@ -1,5 +1,12 @@
 //! Traits, structs and helpers related to solutions/submissions.
 
+pub(crate) mod rules;
+
+#[cfg(test)]
+pub mod tests;
+
+pub use rules::Rules;
+
 /// `Solution` is defined by the language processor used and the path to the
 /// file or directory that contains it.
 #[derive(Debug)]
@@ -20,7 +27,7 @@ impl Solution {
 /// Multiple variants for the solution source type regarding the way it is
 /// structured. Currently supports three types, but is non-exhaustive.
 #[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[non_exhaustive]
 pub enum Source {
     /// A single source file.
@@ -33,3 +40,15 @@ pub enum Source {
     #[cfg_attr(feature = "use-serde", serde(rename = "regex"))]
     Regex { regex: String },
 }
+
+impl Source {
+    /// Argument the language processors are given for the source: the file,
+    /// the files of the directory (`dir/*`) or the regex as it is.
+    pub(crate) fn argument(&self) -> std::ffi::OsString {
+        match self {
+            Source::File(file) => std::ffi::OsString::from(file),
+            Source::Directory(dir) => dir.join("*").into_os_string(),
+            Source::Regex { regex } => std::ffi::OsString::from(regex),
+        }
+    }
+}
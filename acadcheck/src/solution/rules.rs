@@ -0,0 +1,140 @@
+//! Static rules a solution is checked against before it is compiled.
+
+/// Rules on the source of a solution. Patterns are matched against the raw
+/// source, comments and strings included.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rules {
+    /// Regexes that no line of the source may match.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub banned_patterns: Vec<String>,
+    /// Identifiers (e.g. `system` or `std::vector`) the source may not use.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub banned_identifiers: Vec<String>,
+    /// Names of the files the source must have.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub required_files: Vec<String>,
+    /// Identifiers the source must use, such as the functions to implement.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub required_symbols: Vec<String>,
+    /// Most files the source may have.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub max_files: Option<usize>,
+    /// Most bytes the files of the source may have, in total.
+    #[cfg_attr(feature = "use-serde", serde(default))]
+    pub max_size: Option<u64>,
+}
+
+impl Rules {
+    /// Checks the files of a source. Returns every rule it violates, or an
+    /// error if the rules or the source can't be read.
+    pub fn check(&self, source: &crate::solution::Source) -> Result<Vec<String>, anyhow::Error> {
+        // Each regex along with the rule it comes from.
+        let compile = |rule: &String, pattern: String| {
+            regex::Regex::new(&pattern)
+                .map(|r| (rule.clone(), r))
+                .map_err(|e| anyhow::format_err!("Invalid rule {}: {}", rule, e))
+        };
+        let identifier = |i: &String| compile(i, format!(r"\b{}\b", regex::escape(i)));
+
+        let banned = self
+            .banned_patterns
+            .iter()
+            .map(|p| compile(p, p.clone()))
+            .chain(self.banned_identifiers.iter().map(identifier))
+            .collect::<Result<Vec<_>, _>>()?;
+        let required = self
+            .required_symbols
+            .iter()
+            .map(identifier)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let files = files(source)?;
+        let mut violations = Vec::new();
+
+        if let Some(max) = self.max_files {
+            if files.len() > max {
+                violations.push(format!("{} files, at most {} allowed", files.len(), max));
+            }
+        }
+
+        for name in &self.required_files {
+            if !files
+                .iter()
+                .any(|f| f.file_name() == Some(std::ffi::OsStr::new(name)))
+            {
+                violations.push(format!("missing file {}", name));
+            }
+        }
+
+        let mut size = 0;
+        let mut found = vec![false; required.len()];
+
+        for file in &files {
+            let contents =
+                std::fs::read(file).map_err(|e| anyhow::format_err!("{:?}: {}", file, e))?;
+            size += contents.len() as u64;
+            let contents = String::from_utf8_lossy(&contents);
+
+            for (i, line) in contents.lines().enumerate() {
+                for (rule, _) in banned.iter().filter(|(_, r)| r.is_match(line)) {
+                    violations.push(format!(
+                        "banned {} in {}, line {}",
+                        rule,
+                        file.display(),
+                        i + 1
+                    ));
+                }
+            }
+
+            for (found, (_, symbol)) in found.iter_mut().zip(&required) {
+                *found |= symbol.is_match(&contents);
+            }
+        }
+
+        for (_, (symbol, _)) in found.iter().zip(&required).filter(|(found, _)| !**found) {
+            violations.push(format!("missing symbol {}", symbol));
+        }
+
+        if let Some(max) = self.max_size {
+            if size > max {
+                violations.push(format!("{} bytes, at most {} allowed", size, max));
+            }
+        }
+
+        Ok(violations)
+    }
+}
+
+/// Files of a source. Directories are listed recursively, and a regex is the
+/// path the language processors are given for it.
+fn files(source: &crate::solution::Source) -> Result<Vec<std::path::PathBuf>, anyhow::Error> {
+    let mut files = Vec::new();
+
+    match source {
+        crate::solution::Source::File(file) => files.push(file.clone()),
+        crate::solution::Source::Directory(dir) => list(dir, &mut files)?,
+        crate::solution::Source::Regex { .. } => {
+            files.push(std::path::PathBuf::from(source.argument()));
+        }
+        _ => {
+            return Err(anyhow::format_err!("Source type not supported yet!"));
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+fn list(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) -> Result<(), anyhow::Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            list(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use super::*;
+
+#[test]
+fn rules_report_every_violation() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("main.cpp"),
+        "#include <bits/stdc++.h>\n#include \"list.h\"\n\nint main() {\n    std::vector<int> v;\n    system(\"ls\");\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("list.h"), "struct List;\n").unwrap();
+
+    let rules = Rules {
+        banned_patterns: vec![String::from(r"#include\s*<bits/stdc\+\+\.h>")],
+        banned_identifiers: vec![String::from("std::vector"), String::from("system")],
+        required_files: vec![String::from("list.h"), String::from("list.cpp")],
+        required_symbols: vec![String::from("List"), String::from("list_push")],
+        max_files: Some(1),
+        max_size: Some(64),
+    };
+
+    let violations = rules
+        .check(&Source::Directory(dir.path().to_path_buf()))
+        .unwrap();
+    let main = dir.path().join("main.cpp");
+
+    assert_eq!(
+        violations,
+        vec![
+            String::from("2 files, at most 1 allowed"),
+            String::from("missing file list.cpp"),
+            format!(
+                r"banned #include\s*<bits/stdc\+\+\.h> in {}, line 1",
+                main.display()
+            ),
+            format!("banned std::vector in {}, line 5", main.display()),
+            format!("banned system in {}, line 6", main.display()),
+            String::from("missing symbol list_push"),
+            String::from("114 bytes, at most 64 allowed"),
+        ]
+    );
+
+    // A regex source is checked as the file the processors are given.
+    let regex = Source::Regex {
+        regex: main.to_string_lossy().into_owned(),
+    };
+    assert!(
+        rules
+            .check(&regex)
+            .unwrap()
+            .contains(&String::from("missing file list.h"))
+    );
+
+    // The same source passes without the rules.
+    assert!(
+        Rules::default()
+            .check(&Source::Directory(dir.path().to_path_buf()))
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,146 @@
+//! Reading configurations from JSON, TOML or YAML files.
+#![cfg(feature = "use-serde")]
+
+/// File formats a configuration can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl Format {
+    /// Gets the format from the extension of the file. Files without an
+    /// extension are read as JSON.
+    pub(crate) fn from_path(path: &std::path::Path) -> Result<Self, anyhow::Error> {
+        match path.extension().map(|e| e.to_str()) {
+            None | Some(Some("json")) => Ok(Format::Json),
+            Some(Some("toml")) => Ok(Format::Toml),
+            Some(Some("yaml")) | Some(Some("yml")) => Ok(Format::Yaml),
+            _ => Err(anyhow::format_err!(
+                "{:?}: unknown configuration format (expected .json, .toml, .yaml or .yml)",
+                path
+            )),
+        }
+    }
+}
+
+/// Deserializes a file in the given format. On failure, the error names the
+/// field that could not be deserialized along with the line it is on.
+pub(crate) fn from_file<D>(path: &std::path::Path, format: Format) -> Result<D, anyhow::Error>
+where
+    D: serde::de::DeserializeOwned,
+{
+    // Check if path exists.
+    if !path.exists() {
+        return Err(anyhow::format_err!("{:?}: not a valid path!", path));
+    }
+
+    let contents = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(anyhow::format_err!("{:?}: {}", path, e));
+        }
+    };
+
+    let result = match format {
+        Format::Json => {
+            let mut de = serde_json::Deserializer::from_str(&contents);
+            serde_path_to_error::deserialize(&mut de).map_err(located)
+        }
+        Format::Toml => {
+            let de = toml::Deserializer::new(&contents);
+            serde_path_to_error::deserialize(de).map_err(located)
+        }
+        Format::Yaml => {
+            let de = serde_yaml::Deserializer::from_str(&contents);
+            serde_path_to_error::deserialize(de).map_err(located)
+        }
+    };
+
+    result.map_err(|e| anyhow::format_err!("Deserialization of {:?} failed{}", path, e))
+}
+
+/// Formats the path of the failing field and the error of the format.
+fn located<E>(error: serde_path_to_error::Error<E>) -> String
+where
+    E: std::fmt::Display,
+{
+    let field = error.path().to_string();
+    let message = error.inner().to_string();
+
+    // YAML errors already start with the path.
+    let message = message
+        .strip_prefix(&format!("{}: ", field))
+        .unwrap_or(&message);
+
+    if field == "." {
+        format!(": {}", message.trim_end())
+    } else {
+        format!(" at `{}`: {}", field, message.trim_end())
+    }
+}
+
+/// Deserializes a map keyed by test numbers, written either as integers or
+/// as strings (TOML keys are always strings).
+pub(crate) fn test_keys<'de, D, V>(
+    deserializer: D,
+) -> Result<std::collections::BTreeMap<usize, V>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    V: serde::Deserialize<'de>,
+{
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Key(usize);
+
+    impl<'de> serde::Deserialize<'de> for Key {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct KeyVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for KeyVisitor {
+                type Value = Key;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("a test number")
+                }
+
+                fn visit_u64<E>(self, v: u64) -> Result<Key, E>
+                where
+                    E: serde::de::Error,
+                {
+                    usize::try_from(v)
+                        .map(Key)
+                        .map_err(|_| E::custom(format!("test number {} is too large", v)))
+                }
+
+                fn visit_i64<E>(self, v: i64) -> Result<Key, E>
+                where
+                    E: serde::de::Error,
+                {
+                    usize::try_from(v)
+                        .map(Key)
+                        .map_err(|_| E::custom(format!("invalid test number {}", v)))
+                }
+
+                fn visit_str<E>(self, v: &str) -> Result<Key, E>
+                where
+                    E: serde::de::Error,
+                {
+                    v.parse::<usize>()
+                        .map(Key)
+                        .map_err(|_| E::custom(format!("invalid test number {:?}", v)))
+                }
+            }
+
+            deserializer.deserialize_any(KeyVisitor)
+        }
+    }
+
+    let map =
+        <std::collections::BTreeMap<Key, V> as serde::Deserialize>::deserialize(deserializer)?;
+
+    Ok(map.into_iter().map(|(k, v)| (k.0, v)).collect())
+}
@@ -9,18 +9,20 @@ use std::{
 
 pub const HELP_ARG: &str = "--help";
 
-pub const STANDALONE_ARGS: [&str; 1] = ["--memory-footprint"];
+pub const SUBCOMMANDS: [&str; 2] = ["stress", "validate"];
+
+pub const STANDALONE_ARGS: [&str; 2] = ["--memory-footprint", "--stream"];
 
 pub const KEY_PATH_ARGS: [&str; 6] = [
     "--config",
     "--solution-file", // TODO
     "--solution-dir",  // TODO
     "--inrefs",        // TODO
-    "--ins",           // TODO
-    "--refs",          // TODO
+    "--ins",
+    "--refs",
 ];
 
-pub const KEY_STRING_ARGS: [&str; 1] = ["--language"];
+pub const KEY_STRING_ARGS: [&str; 3] = ["--language", "--in-pattern", "--ref-pattern"];
 
 pub const KEY_NUM_ARGS: [&str; 1] = ["--timeout"];
 
@@ -32,11 +34,15 @@ pub enum Arguments {
     /// Path to a json file that contains the in-refs hashmap.
     InRefs(std::path::PathBuf),
     /// Path to directory where the input files are stored.
-    /// They will be matched to the refs as pairs in alphabetic order.
+    /// They will be matched to the refs by the name of the test.
     Ins(std::path::PathBuf),
     /// Path to directory where the ref files are stored.
-    /// They will be matched to the refs as pairs in alphabetic order.
+    /// They will be matched to the ins by the name of the test.
     Refs(std::path::PathBuf),
+    /// Pattern of the input files, where `*` is the name of the test.
+    InPattern(String),
+    /// Pattern of the ref files, where `*` is the name of the test.
+    RefPattern(String),
     /// Puts a timeout for running the solution.
     Timeout(usize),
     /// Printing out to stdout the memory footprint of the program.
@@ -50,6 +56,14 @@ pub enum Arguments {
     SolutionFile(std::path::PathBuf),
     /// The source dir for the solution.
     SolutionDir(std::path::PathBuf),
+    /// Prints the progress events as newline-delimited JSON, followed by the
+    /// result.
+    Stream,
+    /// Stress tests the solution against a brute-force solution instead of
+    /// running the tests.
+    Stress,
+    /// Validates the test inputs instead of running the tests.
+    Validate,
 }
 
 /// Message to stdout on wrong arguments.
@@ -77,22 +91,59 @@ where
     // Argument with index.
     let mut arg = iter.next();
 
-    // Sanity variable to check if the checker will accept the "--config" option.
-    let mut accept_config = true;
-
     // First check the arg is "--help".
     if arg == None {
         exit();
     }
 
-    // Check to see if the arg
-    if arg.unwrap().as_ref().eq(KEY_PATH_ARGS[0]) {
-        arg = iter.next();
-        if arg != None {
-            return vec![Arguments::FromJson(PathBuf::from(arg.unwrap().as_ref()))];
+    let mut parsed = vec![];
+
+    // Subcommands come first.
+    if let Some(a) = &arg {
+        if a.as_ref().eq(SUBCOMMANDS[0]) {
+            parsed.push(Arguments::Stress);
+            arg = iter.next();
+        } else if a.as_ref().eq(SUBCOMMANDS[1]) {
+            parsed.push(Arguments::Validate);
+            arg = iter.next();
         }
-    } else {
-        exit();
     }
-    vec![]
+
+    while let Some(a) = arg {
+        if a.as_ref().eq(KEY_PATH_ARGS[0]) {
+            match iter.next() {
+                Some(path) => parsed.push(Arguments::FromJson(PathBuf::from(path.as_ref()))),
+                None => exit(),
+            }
+        } else if a.as_ref().eq(KEY_PATH_ARGS[4]) {
+            match iter.next() {
+                Some(path) => parsed.push(Arguments::Ins(PathBuf::from(path.as_ref()))),
+                None => exit(),
+            }
+        } else if a.as_ref().eq(KEY_PATH_ARGS[5]) {
+            match iter.next() {
+                Some(path) => parsed.push(Arguments::Refs(PathBuf::from(path.as_ref()))),
+                None => exit(),
+            }
+        } else if a.as_ref().eq(KEY_STRING_ARGS[1]) {
+            match iter.next() {
+                Some(pattern) => parsed.push(Arguments::InPattern(String::from(pattern.as_ref()))),
+                None => exit(),
+            }
+        } else if a.as_ref().eq(KEY_STRING_ARGS[2]) {
+            match iter.next() {
+                Some(pattern) => parsed.push(Arguments::RefPattern(String::from(pattern.as_ref()))),
+                None => exit(),
+            }
+        } else if a.as_ref().eq(STANDALONE_ARGS[1]) {
+            parsed.push(Arguments::Stream);
+        } else {
+            exit_with_arg(a.as_ref());
+            std::process::exit(0);
+        }
+
+        arg = iter.next();
+    }
+
+    parsed
 }
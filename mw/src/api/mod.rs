@@ -214,6 +214,9 @@ async fn submission_run(
     let mut in_refs_files =
         std::collections::BTreeMap::<usize, (Option<NamedTempFile>, Option<NamedTempFile>)>::new();
 
+    // Constraints on the inputs, if the archive has any.
+    let mut constraints: Option<acadcheck::checker::Constraints> = None;
+
     for i in 0..archive.len() {
         // For each file in zip.
         let mut file = archive.by_index(i).unwrap();
@@ -227,6 +230,27 @@ async fn submission_run(
             }
         };
 
+        // If file is the validation spec.
+        if file.name().ends_with(VALIDATION_FILE) {
+            let mut spec = String::new();
+            if let Err(e) = file.read_to_string(&mut spec) {
+                return HttpResponse::InternalServerError().json(Response {
+                    message: e.to_string(),
+                });
+            }
+
+            constraints = match serde_json::from_str(&spec) {
+                Ok(c) => Some(c),
+                Err(e) => {
+                    return HttpResponse::InternalServerError().json(Response {
+                        message: format!("Invalid {}: {}", VALIDATION_FILE, e),
+                    });
+                }
+            };
+
+            continue;
+        }
+
         //If file is an input.
         if in_reg.is_match(file.name()) {
             let mut f = match tempfile::NamedTempFile::new_in(&in_dir) {
@@ -270,6 +294,26 @@ async fn submission_run(
         }
     }
 
+    // Refuse archives with invalid inputs.
+    if let Some(constraints) = constraints {
+        let invalid = in_refs_files
+            .iter()
+            .filter_map(|(k, v)| {
+                let input = v.0.as_ref()?;
+                match constraints.check(input.path()) {
+                    Ok(()) => None,
+                    Err(reason) => Some(format!("{:03}.in: {}", k, reason)),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if !invalid.is_empty() {
+            return HttpResponse::InternalServerError().json(Response {
+                message: format!("Invalid tests archive:\n{}", invalid.join("\n")),
+            });
+        }
+    }
+
     let in_refs = in_refs_files
         .iter()
         .map(|(k, v)| {
@@ -316,8 +360,8 @@ async fn submission_run(
                 }];
                 v
             },
-            output_type: acadcheck::checker::OutputType::None,
             in_refs,
+            ..Default::default()
         },
         processor: (&form.config.processor).into(),
         solution: acadcheck::solution::Source::File(PathBuf::from(format!(
@@ -333,6 +377,13 @@ async fn submission_run(
         ))),
         out_dir: sandbox_config.out.clone(),
         security: Some(sandbox_config.security.clone()),
+        discover: None,
+        generate: None,
+        stress: None,
+        validator: None,
+        time_limits: form.config.time_limit.per_language.clone(),
+        xunit: None,
+        rules: None,
     };
 
     // Tempfile to send to checker.
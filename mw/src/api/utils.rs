@@ -25,6 +25,10 @@ pub const PROVIDER_NAME: &str = "CustomEnvironment";
 pub(crate) const IN_REGEX: &str = "in/[0-9][0-9][0-9].in";
 pub(crate) const REF_REGEX: &str = "ref/[0-9][0-9][0-9].ref";
 
+/// Optional constraints on the inputs, checked before running any
+/// submission.
+pub(crate) const VALIDATION_FILE: &str = "validation.json";
+
 /// Basic response message on any response with status other than 200 OK.
 #[derive(serde::Deserialize, serde::Serialize)]
 pub(crate) struct Response {
@@ -43,6 +47,10 @@ pub(crate) struct UploadSolution {
 #[non_exhaustive]
 pub(crate) struct UploadTimeLimit {
     pub(crate) secs: u64,
+    /// Adjustments of the limit by language, such as `"python": { "multiplier": 3 }`.
+    #[serde(default)]
+    pub(crate) per_language:
+        std::collections::BTreeMap<String, acadcheck::acadchecker::config::TimeAdjustment>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]